@@ -0,0 +1,29 @@
+-- Add migration script here
+
+PRAGMA foreign_keys = ON;
+
+-- Clip downloads waiting for (or being processed by) the background worker.
+-- The id doubles as the audio id and the `data/<id>/` directory name.
+CREATE TABLE IF NOT EXISTS download_queue (
+    id TEXT PRIMARY KEY,
+    userId TEXT NOT NULL,
+    url TEXT NOT NULL,
+    title TEXT NOT NULL,
+    description TEXT,
+    thumbnail TEXT,
+    startTime INTEGER NOT NULL,
+    endTime INTEGER NOT NULL,
+    provider TEXT NOT NULL,
+    tag TEXT,
+    status TEXT NOT NULL DEFAULT 'queued' CHECK (status IN ('queued', 'running', 'done', 'failed', 'cancelled')),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    error TEXT,
+    nextAttemptAt INTEGER NOT NULL DEFAULT (unixepoch()),
+    createdAt TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updatedAt TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (userId) REFERENCES user(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS download_queue_status_idx ON download_queue (status, nextAttemptAt);
+
+ALTER TABLE app_settings ADD COLUMN maxConcurrentDownloads INTEGER NOT NULL DEFAULT 2;
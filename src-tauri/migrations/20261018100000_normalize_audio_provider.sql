@@ -0,0 +1,30 @@
+-- Add migration script here
+
+PRAGMA foreign_keys = ON;
+
+-- `provider` used to hold whatever the frontend sent (the oEmbed provider name,
+-- e.g. 'YouTube', or an empty string). Map it onto the values of `Provider`.
+UPDATE audio
+SET provider = CASE
+    WHEN lower(provider) IN ('youtube', 'ytdlp', 'http', 'local') THEN lower(provider)
+    WHEN url LIKE '%youtube.com/%' OR url LIKE '%youtu.be/%' THEN 'youtube'
+    ELSE 'ytdlp'
+END;
+
+UPDATE download_queue
+SET provider = lower(provider)
+WHERE lower(provider) IN ('youtube', 'ytdlp', 'http', 'local');
+
+CREATE TRIGGER IF NOT EXISTS audio_provider_insert_check
+BEFORE INSERT ON audio
+WHEN NEW.provider NOT IN ('youtube', 'ytdlp', 'http', 'local')
+BEGIN
+    SELECT RAISE(ABORT, 'invalid audio provider');
+END;
+
+CREATE TRIGGER IF NOT EXISTS audio_provider_update_check
+BEFORE UPDATE OF provider ON audio
+WHEN NEW.provider NOT IN ('youtube', 'ytdlp', 'http', 'local')
+BEGIN
+    SELECT RAISE(ABORT, 'invalid audio provider');
+END;
@@ -0,0 +1,8 @@
+-- Add migration script here
+
+PRAGMA foreign_keys = ON;
+
+-- sha256 of data/<id>/audio.m4a, used to spot the same clip downloaded twice
+ALTER TABLE audio ADD COLUMN contentHash TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_audio_user_content_hash ON audio(userId, contentHash);
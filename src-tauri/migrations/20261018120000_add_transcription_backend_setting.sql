@@ -0,0 +1,7 @@
+-- Add migration script here
+
+PRAGMA foreign_keys = ON;
+
+-- 'service' is the WhisperX HTTP service at modelProxy, 'sidecar' the bundled whip_v2 binary
+ALTER TABLE app_settings ADD COLUMN transcriptionBackend TEXT NOT NULL DEFAULT 'service'
+    CHECK (transcriptionBackend IN ('sidecar', 'service'));
@@ -0,0 +1,19 @@
+-- Add migration script here
+
+PRAGMA foreign_keys = ON;
+
+-- Latest transcription of each audio, kept so the UI can pick up its state after a reload.
+CREATE TABLE IF NOT EXISTS transcription_job (
+    audioId TEXT PRIMARY KEY,
+    userId TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'queued' CHECK (status IN ('queued', 'running', 'done', 'failed', 'cancelled')),
+    model TEXT NOT NULL,
+    language TEXT NOT NULL,
+    progress REAL,
+    message TEXT,
+    error TEXT,
+    createdAt TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updatedAt TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (audioId) REFERENCES audio(id) ON DELETE CASCADE,
+    FOREIGN KEY (userId) REFERENCES user(id) ON DELETE CASCADE
+);
@@ -0,0 +1,6 @@
+-- Add migration script here
+
+PRAGMA foreign_keys = ON;
+
+-- Spoken language of the audio (ISO 639 code), detected or chosen when transcribing
+ALTER TABLE audio ADD COLUMN language TEXT;
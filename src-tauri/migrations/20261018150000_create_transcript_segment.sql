@@ -0,0 +1,19 @@
+-- Add migration script here
+
+PRAGMA foreign_keys = ON;
+
+-- Transcript of an audio, one row per subtitle segment. segmentIndex is the
+-- index bookmark.bookmarkId and dictation.dictationId refer to.
+CREATE TABLE IF NOT EXISTS transcript_segment (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    audioId TEXT NOT NULL,
+    segmentIndex INTEGER NOT NULL,
+    startTime REAL NOT NULL,
+    endTime REAL NOT NULL,
+    text TEXT NOT NULL,
+    words TEXT, -- JSON array of word timings, NULL if the backend didn't provide them
+    createdAt TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updatedAt TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (audioId) REFERENCES audio(id) ON DELETE CASCADE,
+    UNIQUE(audioId, segmentIndex)
+);
@@ -0,0 +1,25 @@
+-- Add migration script here
+
+PRAGMA foreign_keys = ON;
+
+-- Every transcript edit replaces oldSegments (starting at segmentIndex) with newSegments.
+-- Both are JSON arrays of segments, so an edit can be undone by swapping them back.
+-- oldReferences holds the bookmark, dictation and dictation_attempt rows of the edited
+-- range before the edit, as a JSON object of arrays. A merge drops references that end
+-- up on the same segment, undo puts them back from here.
+CREATE TABLE IF NOT EXISTS transcript_revision (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    audioId TEXT NOT NULL,
+    userId TEXT NOT NULL,
+    action TEXT NOT NULL CHECK (action IN ('edit_text', 'split', 'merge', 'retime')),
+    segmentIndex INTEGER NOT NULL,
+    oldSegments TEXT NOT NULL,
+    newSegments TEXT NOT NULL,
+    oldReferences TEXT NOT NULL DEFAULT '{}',
+    undone INTEGER NOT NULL DEFAULT 0 CHECK (undone IN (0, 1)),
+    createdAt TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (audioId) REFERENCES audio(id) ON DELETE CASCADE,
+    FOREIGN KEY (userId) REFERENCES user(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS transcript_revision_audio_idx ON transcript_revision (audioId, undone, id);
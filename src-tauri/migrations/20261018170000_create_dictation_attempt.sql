@@ -0,0 +1,21 @@
+-- Add migration script here
+
+PRAGMA foreign_keys = ON;
+
+-- Every dictation the user typed for a segment (segmentIndex, like dictation.dictationId),
+-- with the transcript text at the time and the word marks it was scored with.
+CREATE TABLE IF NOT EXISTS dictation_attempt (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    userId TEXT NOT NULL,
+    audioId TEXT NOT NULL,
+    segmentIndex INTEGER NOT NULL,
+    expectedText TEXT NOT NULL,
+    typedText TEXT NOT NULL,
+    marks TEXT NOT NULL, -- JSON array of word marks
+    accuracy REAL NOT NULL CHECK (accuracy >= 0 AND accuracy <= 1),
+    createdAt TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (audioId) REFERENCES audio(id) ON DELETE CASCADE,
+    FOREIGN KEY (userId) REFERENCES user(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS dictation_attempt_segment_idx ON dictation_attempt (userId, audioId, segmentIndex);
@@ -0,0 +1,13 @@
+-- Add migration script here
+
+PRAGMA foreign_keys = ON;
+
+-- Bookmarks double as spaced-repetition review cards (SM-2).
+-- A NULL dueAt is a card that was never reviewed and is due right away.
+ALTER TABLE bookmark ADD COLUMN easeFactor REAL NOT NULL DEFAULT 2.5;
+ALTER TABLE bookmark ADD COLUMN intervalDays INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE bookmark ADD COLUMN repetitions INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE bookmark ADD COLUMN dueAt TIMESTAMP;
+ALTER TABLE bookmark ADD COLUMN lastReviewedAt TIMESTAMP;
+
+CREATE INDEX IF NOT EXISTS bookmark_due_idx ON bookmark (userId, dueAt);
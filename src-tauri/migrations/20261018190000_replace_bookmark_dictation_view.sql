@@ -0,0 +1,42 @@
+-- Add migration script here
+
+PRAGMA foreign_keys = ON;
+
+-- The previous view joined bookmark and dictation on userId/audioId only, pairing
+-- every bookmark of an audio with every dictation of it. This one has one row per
+-- segment index that is bookmarked, dictated or has dictation attempts.
+DROP VIEW IF EXISTS bookmark_dictation_view;
+
+CREATE VIEW bookmark_dictation_view AS
+WITH segments AS (
+    SELECT userId, audioId, bookmarkId AS segmentIndex FROM bookmark
+    UNION
+    SELECT userId, audioId, dictationId FROM dictation
+    UNION
+    SELECT userId, audioId, segmentIndex FROM dictation_attempt
+)
+SELECT
+    s.userId,
+    s.audioId,
+    s.segmentIndex,
+    b.id AS bookmark_id,
+    b.createdAt AS bookmark_created_at,
+    d.id AS dictation_id,
+    d.createdAt AS dictation_created_at,
+    (
+        SELECT COUNT(*) FROM dictation_attempt a
+        WHERE a.userId = s.userId AND a.audioId = s.audioId AND a.segmentIndex = s.segmentIndex
+    ) AS attempt_count,
+    la.accuracy AS latest_accuracy,
+    la.createdAt AS latest_attempt_at
+FROM segments s
+LEFT JOIN bookmark b
+    ON b.userId = s.userId AND b.audioId = s.audioId AND b.bookmarkId = s.segmentIndex
+LEFT JOIN dictation d
+    ON d.userId = s.userId AND d.audioId = s.audioId AND d.dictationId = s.segmentIndex
+LEFT JOIN dictation_attempt la ON la.id = (
+    SELECT a.id FROM dictation_attempt a
+    WHERE a.userId = s.userId AND a.audioId = s.audioId AND a.segmentIndex = s.segmentIndex
+    ORDER BY a.id DESC
+    LIMIT 1
+);
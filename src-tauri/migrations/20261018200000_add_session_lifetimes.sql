@@ -0,0 +1,18 @@
+-- Add migration script here
+
+PRAGMA foreign_keys = ON;
+
+-- A session expires after sessionIdleSeconds without use, and at the latest
+-- sessionAbsoluteSeconds after it was created
+ALTER TABLE app_settings ADD COLUMN sessionIdleSeconds INTEGER NOT NULL DEFAULT 86400
+    CHECK (sessionIdleSeconds > 0);
+ALTER TABLE app_settings ADD COLUMN sessionAbsoluteSeconds INTEGER NOT NULL DEFAULT 2592000
+    CHECK (sessionAbsoluteSeconds > 0);
+
+ALTER TABLE session ADD COLUMN absoluteExpiresAt INTEGER NOT NULL DEFAULT 0;
+
+UPDATE session SET absoluteExpiresAt = unixepoch(createdAt) + 2592000;
+
+CREATE INDEX IF NOT EXISTS session_expires_at_idx ON session (expiresAt);
+CREATE INDEX IF NOT EXISTS session_user_idx ON session (userId);
+CREATE INDEX IF NOT EXISTS verification_expires_at_idx ON verification (expiresAt);
@@ -55,7 +55,13 @@ def save_data(filename: str, data):
 
 
 def transcribe(
-    *, model_size: str, model_path: str, file_path: str, lang: str, output: str
+    *,
+    model_size: str,
+    model_path: str,
+    file_path: str,
+    lang: str | None,
+    output: str,
+    initial_prompt: str | None,
 ):
     device, compute_type = get_device_config()
 
@@ -70,6 +76,7 @@ def transcribe(
         word_timestamps=True,
         vad_filter=False,
         log_progress=True,
+        initial_prompt=initial_prompt,
     )
 
     segments_data = handle_segments(segments=segments)
@@ -86,13 +93,16 @@ def run(
     model_path: str,
     lang: str | None = "en",
     output: str,
+    initial_prompt: str | None = None,
 ):
     transcribe(
         file_path=file,
         model_size=model or "base.en",
         model_path=model_path,
-        lang=lang or "en",
+        # "auto" lets the model detect the language
+        lang=None if lang == "auto" else lang or "en",
         output=output,
+        initial_prompt=initial_prompt or None,
     )
 
 
@@ -111,12 +121,18 @@ if __name__ == "__main__":
         help="Path of Model (e.g. base.en)",
     )
     parser.add_argument(
-        "--lang", type=str, default="en", help="Language for transcribe"
+        "--lang", type=str, default="en", help="Language for transcribe, or auto"
     )
 
     parser.add_argument(
         "--output", type=str, required=True, help="Path of the transcribe file"
     )
+    parser.add_argument(
+        "--initial_prompt",
+        type=str,
+        default=None,
+        help="Text to condition the first window on (e.g. names, terms)",
+    )
     args = parser.parse_args()
 
     run(
@@ -125,4 +141,5 @@ if __name__ == "__main__":
         model_path=args.model_path,
         lang=args.lang,
         output=args.output,
+        initial_prompt=args.initial_prompt,
     )
@@ -0,0 +1,126 @@
+use std::{sync::Mutex, time::Duration};
+
+use tauri::{AppHandle, Manager};
+
+use crate::{
+    db::Db,
+    error::AppError,
+    query::{
+        audio::{get_audio, AudioItem},
+        store::{delete_store_token, get_store_token, set_store_token},
+        user::{get_user_by_session_token, purge_expired_sessions, SessionWithUser},
+    },
+    DbState,
+};
+
+const SWEEP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// The session token of the signed in user. Commands authenticate with it
+/// instead of taking the token from the frontend, it is mirrored in
+/// `cookie.json` so the session survives a restart.
+#[derive(Default)]
+pub struct SessionState {
+    token: Mutex<Option<String>>,
+}
+
+impl SessionState {
+    fn get(&self) -> Option<String> {
+        self.token.lock().unwrap().clone()
+    }
+
+    fn set(&self, token: Option<String>) {
+        *self.token.lock().unwrap() = token;
+    }
+}
+
+/// Makes `token` the current session, in memory and in the store.
+pub fn bind_session(app_handle: &AppHandle, token: &str) -> Result<(), AppError> {
+    set_store_token(app_handle.clone(), token)?;
+    app_handle
+        .state::<SessionState>()
+        .set(Some(token.to_string()));
+
+    Ok(())
+}
+
+/// Forgets the current session and returns its token, if there was one.
+pub fn unbind_session(app_handle: &AppHandle) -> Result<Option<String>, AppError> {
+    let state = app_handle.state::<SessionState>();
+    let stored = delete_store_token(app_handle)?;
+    let token = state.get().or(stored);
+    state.set(None);
+
+    Ok(token)
+}
+
+/// The current session token, restored from the store after a restart.
+fn current_token(app_handle: &AppHandle) -> Option<String> {
+    let state = app_handle.state::<SessionState>();
+
+    state.get().or_else(|| {
+        let token = get_store_token(app_handle).ok()?;
+        state.set(Some(token.clone()));
+        Some(token)
+    })
+}
+
+/// Resolves the current session, `None` when signed out. An expired session
+/// is unbound so the next command doesn't look it up again.
+pub async fn current_user(
+    app_handle: &AppHandle,
+    db: &Db,
+) -> Result<Option<SessionWithUser>, AppError> {
+    let Some(token) = current_token(app_handle) else {
+        return Ok(None);
+    };
+
+    let user = get_user_by_session_token(db, token).await?;
+    if user.is_none() {
+        unbind_session(app_handle)?;
+    }
+
+    Ok(user)
+}
+
+/// Resolves the signed in user of the current session.
+pub async fn require_user(app_handle: &AppHandle, db: &Db) -> Result<SessionWithUser, AppError> {
+    current_user(app_handle, db)
+        .await?
+        .ok_or(AppError::unauthorized())
+}
+
+/// Resolves the signed in user and the audio `audio_id`, which they must own.
+pub async fn require_audio(
+    app_handle: &AppHandle,
+    db: &Db,
+    audio_id: &str,
+) -> Result<(SessionWithUser, AudioItem), AppError> {
+    let user = require_user(app_handle, db).await?;
+
+    let audio = get_audio(db, user.user_id.clone(), audio_id.to_string())
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::RowNotFound => AppError::NotFound(format!("Audio {} not found", audio_id)),
+            e => e.into(),
+        })?;
+
+    Ok((user, audio))
+}
+
+/// Purges expired sessions and verifications at startup and then every hour,
+/// the ones nobody presents again would otherwise stay forever.
+pub fn start_session_sweeper(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let db = app_handle.state::<DbState>().db.clone();
+
+        loop {
+            match purge_expired_sessions(&db).await {
+                Ok(0) => {}
+                Ok(count) => println!("🧹 Purged {} expired session(s)", count),
+                Err(e) => println!("❌ Failed to purge expired sessions: {}", e),
+            }
+
+            tokio::time::sleep(SWEEP_INTERVAL).await;
+        }
+    });
+}
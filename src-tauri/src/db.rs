@@ -41,3 +41,18 @@ pub async fn setup_db(app: &App) -> Db {
 
     db
 }
+
+/// An in-memory database with all migrations applied.
+#[cfg(test)]
+pub(crate) async fn test_db() -> Db {
+    // Every connection to `:memory:` opens a new empty database
+    let db = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+
+    sqlx::migrate!("./migrations").run(&db).await.unwrap();
+
+    db
+}
@@ -0,0 +1,336 @@
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::{
+    auth::{require_audio, require_user},
+    error::AppError,
+    query::dictation_attempt::{
+        create_dictation_attempt, get_dictation_attempts as get_attempts, DictationAttempt,
+    },
+    transcript::load_transcript,
+    DbState,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "lowercase")]
+pub enum WordMarkKind {
+    Correct,
+    /// In the transcript but not typed
+    Missing,
+    /// Typed but not in the transcript
+    Extra,
+    /// Typed close to the transcript word, but not quite
+    Misspelled,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct WordMark {
+    pub kind: WordMarkKind,
+    pub expected: Option<String>,
+    pub typed: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DictationScore {
+    pub marks: Vec<WordMark>,
+    /// Correct words over transcript words plus extra words, from 0 to 1
+    pub accuracy: f64,
+}
+
+// "'s" is also the possessive, so it's only expanded for these words
+const CONTRACTIONS: [(&str, &str); 15] = [
+    ("can't", "can not"),
+    ("cannot", "can not"),
+    ("won't", "will not"),
+    ("shan't", "shall not"),
+    ("let's", "let us"),
+    ("i'm", "i am"),
+    ("it's", "it is"),
+    ("he's", "he is"),
+    ("she's", "she is"),
+    ("that's", "that is"),
+    ("what's", "what is"),
+    ("there's", "there is"),
+    ("here's", "here is"),
+    ("who's", "who is"),
+    ("where's", "where is"),
+];
+
+const CONTRACTION_SUFFIXES: [(&str, &str); 5] = [
+    ("n't", " not"),
+    ("'re", " are"),
+    ("'ve", " have"),
+    ("'ll", " will"),
+    ("'d", " would"),
+];
+
+/// Lowercases, drops punctuation and expands contractions, so "Don't!" and
+/// "do not" compare equal.
+pub(crate) fn normalize_words(text: &str) -> Vec<String> {
+    let text = text.to_lowercase().replace(['\u{2019}', '\u{2018}'], "'");
+    let mut words = Vec::new();
+
+    for token in text.split_whitespace() {
+        let token: String = token
+            .chars()
+            .filter(|c| c.is_alphanumeric() || *c == '\'')
+            .collect();
+        let token = token.trim_matches('\'');
+        if token.is_empty() {
+            continue;
+        }
+
+        let expanded = CONTRACTIONS
+            .iter()
+            .find(|(contraction, _)| *contraction == token)
+            .map(|(_, expansion)| expansion.to_string())
+            .or_else(|| {
+                CONTRACTION_SUFFIXES
+                    .iter()
+                    .find(|(suffix, _)| token.len() > suffix.len() && token.ends_with(suffix))
+                    .map(|(suffix, expansion)| {
+                        format!("{}{}", &token[..token.len() - suffix.len()], expansion)
+                    })
+            })
+            .unwrap_or(token.to_string());
+
+        words.extend(
+            expanded
+                .split_whitespace()
+                .map(|word| word.replace('\'', "")),
+        );
+    }
+
+    words
+}
+
+fn char_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+/// A typo rather than another word: at most a third of the letters differ.
+fn is_misspelling(expected: &str, typed: &str) -> bool {
+    char_distance(expected, typed) <= (expected.chars().count() / 3).max(1)
+}
+
+/// Aligns the typed words to the transcript with a word-level edit distance
+/// and marks every word.
+pub(crate) fn score_dictation(expected_text: &str, typed_text: &str) -> DictationScore {
+    let expected = normalize_words(expected_text);
+    let typed = normalize_words(typed_text);
+    let (rows, columns) = (expected.len(), typed.len());
+
+    // A misspelling costs 1, a different word as much as a missing plus an extra one
+    let substitution_cost = |i: usize, j: usize| {
+        if expected[i] == typed[j] {
+            0
+        } else if is_misspelling(&expected[i], &typed[j]) {
+            1
+        } else {
+            2
+        }
+    };
+
+    let mut costs = vec![vec![0; columns + 1]; rows + 1];
+    for (i, row) in costs.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    costs[0] = (0..=columns).collect();
+    for i in 1..=rows {
+        for j in 1..=columns {
+            costs[i][j] = (costs[i - 1][j - 1] + substitution_cost(i - 1, j - 1))
+                .min(costs[i - 1][j] + 1)
+                .min(costs[i][j - 1] + 1);
+        }
+    }
+
+    let mark = |kind, expected: Option<&String>, typed: Option<&String>| WordMark {
+        kind,
+        expected: expected.cloned(),
+        typed: typed.cloned(),
+    };
+
+    let mut marks = Vec::new();
+    let (mut i, mut j) = (rows, columns);
+    while i > 0 || j > 0 {
+        if i > 0 && j > 0 {
+            let cost = substitution_cost(i - 1, j - 1);
+            if cost < 2 && costs[i][j] == costs[i - 1][j - 1] + cost {
+                let kind = if cost == 0 {
+                    WordMarkKind::Correct
+                } else {
+                    WordMarkKind::Misspelled
+                };
+                marks.push(mark(kind, Some(&expected[i - 1]), Some(&typed[j - 1])));
+                i -= 1;
+                j -= 1;
+                continue;
+            }
+        }
+
+        if j > 0 && (i == 0 || costs[i][j] == costs[i][j - 1] + 1) {
+            marks.push(mark(WordMarkKind::Extra, None, Some(&typed[j - 1])));
+            j -= 1;
+        } else {
+            marks.push(mark(WordMarkKind::Missing, Some(&expected[i - 1]), None));
+            i -= 1;
+        }
+    }
+    marks.reverse();
+
+    let count = |kind| marks.iter().filter(|mark| mark.kind == kind).count();
+    let total = rows + count(WordMarkKind::Extra);
+    let accuracy = if total == 0 {
+        1.0
+    } else {
+        count(WordMarkKind::Correct) as f64 / total as f64
+    };
+
+    DictationScore { marks, accuracy }
+}
+
+/// Scores `typed_text` against transcript segment `segment_id` (the segment
+/// index, like `dictationId`) and stores the attempt.
+#[tauri::command]
+#[specta::specta]
+pub async fn submit_dictation_attempt(
+    app_handle: AppHandle,
+    state: tauri::State<'_, DbState>,
+    audio_id: String,
+    segment_id: i64,
+    typed_text: String,
+) -> Result<DictationAttempt, AppError> {
+    let db = &state.db;
+
+    let (user, _) = require_audio(&app_handle, db, &audio_id).await?;
+
+    let transcript = load_transcript(&app_handle, db, &user.user_id, &audio_id).await?;
+    let segment = usize::try_from(segment_id)
+        .ok()
+        .and_then(|index| transcript.get(index))
+        .ok_or(AppError::NotFound(format!(
+            "Segment {} does not exist",
+            segment_id
+        )))?;
+
+    let score = score_dictation(&segment.text, &typed_text);
+
+    Ok(create_dictation_attempt(
+        db,
+        &user.user_id,
+        &audio_id,
+        segment_id,
+        &segment.text,
+        &typed_text,
+        &score,
+    )
+    .await?)
+}
+
+/// Attempt history of the audio, newest first, optionally for one segment.
+#[tauri::command]
+#[specta::specta]
+pub async fn get_dictation_attempts(
+    app_handle: AppHandle,
+    state: tauri::State<'_, DbState>,
+    audio_id: String,
+    segment_id: Option<i64>,
+) -> Result<Vec<DictationAttempt>, AppError> {
+    let db = &state.db;
+
+    let user = require_user(&app_handle, db).await?;
+
+    Ok(get_attempts(db, &user.user_id, &audio_id, segment_id).await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(score: &DictationScore) -> Vec<(WordMarkKind, Option<&str>, Option<&str>)> {
+        score
+            .marks
+            .iter()
+            .map(|mark| (mark.kind, mark.expected.as_deref(), mark.typed.as_deref()))
+            .collect()
+    }
+
+    #[test]
+    fn normalizes_case_punctuation_and_contractions() {
+        assert_eq!(
+            normalize_words("Don't stop, it\u{2019}s TIME! We'll see; Tom's car."),
+            vec!["do", "not", "stop", "it", "is", "time", "we", "will", "see", "toms", "car"]
+        );
+        assert_eq!(normalize_words("I can't"), normalize_words("i cannot"));
+        assert_eq!(normalize_words(" -- ... "), Vec::<String>::new());
+    }
+
+    #[test]
+    fn perfect_dictation() {
+        let score = score_dictation("Don't stop me now!", "do not stop me now");
+
+        assert_eq!(score.accuracy, 1.0);
+        assert!(score
+            .marks
+            .iter()
+            .all(|mark| mark.kind == WordMarkKind::Correct));
+    }
+
+    #[test]
+    fn marks_missing_extra_and_misspelled_words() {
+        use WordMarkKind::*;
+
+        let score = score_dictation("the quick brown fox jumps", "the quik fox really jumps");
+
+        assert_eq!(
+            kinds(&score),
+            vec![
+                (Correct, Some("the"), Some("the")),
+                (Misspelled, Some("quick"), Some("quik")),
+                (Missing, Some("brown"), None),
+                (Correct, Some("fox"), Some("fox")),
+                (Extra, None, Some("really")),
+                (Correct, Some("jumps"), Some("jumps")),
+            ]
+        );
+        // 3 correct out of 5 transcript words and 1 extra
+        assert_eq!(score.accuracy, 0.5);
+    }
+
+    #[test]
+    fn different_word_is_missing_and_extra() {
+        use WordMarkKind::*;
+
+        let score = score_dictation("a cat", "a dog");
+
+        assert_eq!(
+            kinds(&score),
+            vec![
+                (Correct, Some("a"), Some("a")),
+                (Missing, Some("cat"), None),
+                (Extra, None, Some("dog")),
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_inputs() {
+        assert_eq!(score_dictation("", "").accuracy, 1.0);
+        assert_eq!(score_dictation("hello there", "").accuracy, 0.0);
+        assert_eq!(score_dictation("", "hello").accuracy, 0.0);
+    }
+}
@@ -0,0 +1,99 @@
+use std::fmt;
+
+use serde::Serialize;
+
+/// The error of every command, serialized as `{ "kind": "notFound", "message": "..." }`
+/// so the frontend can tell a signed out user from a missing item or a failed download.
+#[derive(Debug, Clone, PartialEq, Serialize, specta::Type)]
+#[serde(tag = "kind", content = "message", rename_all = "camelCase")]
+pub enum AppError {
+    /// No session, or it expired
+    Unauthorized(String),
+    NotFound(String),
+    /// The arguments of the command are invalid
+    Validation(String),
+    Db(String),
+    Io(String),
+    /// A sidecar, the transcription backend or another external service failed
+    Backend(String),
+}
+
+impl AppError {
+    pub fn unauthorized() -> Self {
+        AppError::Unauthorized("Invalid or expired session".to_string())
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            AppError::Unauthorized(message)
+            | AppError::NotFound(message)
+            | AppError::Validation(message)
+            | AppError::Db(message)
+            | AppError::Io(message)
+            | AppError::Backend(message) => message,
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<sqlx::Error> for AppError {
+    fn from(error: sqlx::Error) -> Self {
+        match error {
+            sqlx::Error::RowNotFound => AppError::NotFound("Item not found".to_string()),
+            error => AppError::Db(error.to_string()),
+        }
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(error: std::io::Error) -> Self {
+        AppError::Io(error.to_string())
+    }
+}
+
+impl From<tauri::Error> for AppError {
+    fn from(error: tauri::Error) -> Self {
+        AppError::Backend(error.to_string())
+    }
+}
+
+/// Sidecars, providers and transcription backends report their failures as strings.
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        AppError::Backend(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serializes_as_tagged_object() {
+        let error = AppError::NotFound("Audio abc not found".to_string());
+
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            serde_json::json!({ "kind": "notFound", "message": "Audio abc not found" })
+        );
+    }
+
+    #[test]
+    fn missing_rows_are_not_found() {
+        assert!(matches!(
+            AppError::from(sqlx::Error::RowNotFound),
+            AppError::NotFound(_)
+        ));
+        assert!(matches!(
+            AppError::from(sqlx::Error::PoolTimedOut),
+            AppError::Db(_)
+        ));
+    }
+}
@@ -1,12 +1,23 @@
 use db::{setup_db, Db};
 use tauri::Manager;
 
+mod auth;
 mod config;
 mod db;
+mod dictation;
+mod error;
+mod local_media;
 mod model;
+mod provider;
 mod query;
+mod queue;
+mod review;
 mod server;
 mod service;
+mod stats;
+mod subtitle;
+mod transcript;
+mod transcription;
 mod yt;
 
 use tauri_specta::{collect_commands, Builder};
@@ -34,15 +45,38 @@ pub fn run() {
     ts_build = ts_build.commands(collect_commands![
         greet,
         yt::download_yt_sections,
-        model::start_transcribe,
-        model::start_transcribe_service,
-        model::start_transcribe_service_streaming,
+        yt::cancel_download,
+        yt::list_downloads,
+        yt::fetch_video_info,
+        yt::download_yt_captions,
+        local_media::import_local_media,
+        queue::enqueue_download,
+        queue::get_download_queue,
+        model::transcribe,
+        model::get_transcription_status,
+        model::cancel_transcription,
+        transcript::get_transcript,
+        transcript::edit_transcript_segment,
+        transcript::split_transcript_segment,
+        transcript::merge_transcript_segments,
+        transcript::retime_transcript_segment,
+        transcript::undo_transcript_edit,
+        transcript::get_transcript_revisions,
+        transcript::export_transcript,
+        transcript::import_subtitles,
+        dictation::submit_dictation_attempt,
+        dictation::get_dictation_attempts,
+        review::get_due_reviews,
+        review::grade_review,
+        stats::get_practice_stats,
         model::check_model_health,
         server::start_oauth_server,
         server::stop_oauth_server,
-        query::commands::handle_login,
         query::commands::check_persist_user,
         query::commands::logout_user,
+        query::commands::logout_all_sessions,
+        query::commands::list_sessions,
+        query::commands::revoke_session,
         query::commands::handle_create_audio,
         query::commands::handle_get_audio_list,
         query::commands::handle_get_audio_item,
@@ -72,14 +106,36 @@ pub fn run() {
 
     builder
         .setup(|app| {
+            app.manage(yt::DownloadState::default());
+            app.manage(queue::DownloadQueueState::default());
+            app.manage(model::TranscriptionJobs::default());
+            app.manage(auth::SessionState::default());
+            app.manage(server::PendingSignIns::default());
+            app.manage(service::google::GoogleOAuthClient::new(
+                service::google::GoogleOAuthConfig::from_env(),
+            ));
+
             let app_handle_db = app.handle().clone();
+            let app_handle_queue = app.handle().clone();
+            let app_handle_transcript = app.handle().clone();
+            let app_handle_sessions = app.handle().clone();
             //
             tauri::async_runtime::block_on(async move {
                 let db = setup_db(&app).await;
 
+                if let Err(e) =
+                    query::transcription_job::fail_interrupted_transcription_jobs(&db).await
+                {
+                    println!("❌ Failed to clean up transcription jobs: {}", e);
+                }
+
                 app_handle_db.manage(DbState { db });
             });
 
+            queue::start_download_worker(app_handle_queue);
+            auth::start_session_sweeper(app_handle_sessions);
+            tauri::async_runtime::spawn(transcript::import_subtitle_files(app_handle_transcript));
+
             Ok(())
         })
         .invoke_handler(ts_build.invoke_handler())
@@ -0,0 +1,161 @@
+use std::path::Path;
+
+use tauri::AppHandle;
+use tauri_plugin_shell::ShellExt;
+use uuid::Uuid;
+
+use crate::{
+    auth::require_user,
+    config::get_data_path,
+    error::AppError,
+    provider::Provider,
+    query::{
+        audio::{create_audio, get_audio, AudioItem},
+        commands::{remove_dir_all_safe, store_audio_content_hash},
+    },
+    DbState,
+};
+
+/// Reads the `Duration: HH:MM:SS.cc` line ffmpeg prints for its input, in seconds.
+pub(crate) fn parse_ffmpeg_duration(stderr: &str) -> Option<f64> {
+    let line = stderr
+        .lines()
+        .find(|line| line.trim_start().starts_with("Duration:"))?;
+    let value = line.trim_start()["Duration:".len()..]
+        .split(',')
+        .next()?
+        .trim();
+
+    let mut parts = value.split(':');
+    let hours: f64 = parts.next()?.parse().ok()?;
+    let minutes: f64 = parts.next()?.parse().ok()?;
+    let seconds: f64 = parts.next()?.parse().ok()?;
+
+    Some(hours * 3600.0 + minutes * 60.0 + seconds)
+}
+
+async fn transcode_to_m4a(
+    app_handle: &AppHandle,
+    input: &str,
+    output: &str,
+) -> Result<f64, String> {
+    let output = app_handle
+        .shell()
+        .sidecar("ffmpeg")
+        .map_err(|e| format!("can't find ffmpeg sidecar: {}", e))?
+        .args([
+            "-hide_banner",
+            "-nostdin",
+            "-y",
+            "-i",
+            input,
+            "-vn",
+            "-c:a",
+            "aac",
+            "-b:a",
+            "192k",
+            output,
+        ])
+        .output()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    if !output.status.success() {
+        let reason = stderr.lines().last().unwrap_or("unknown error");
+        return Err(format!("Failed to transcode {}: {}", input, reason));
+    }
+
+    parse_ffmpeg_duration(&stderr).ok_or(format!("Failed to read the duration of {}", input))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn import_local_media(
+    app_handle: AppHandle,
+    state: tauri::State<'_, DbState>,
+    path: String,
+    title: String,
+) -> Result<AudioItem, AppError> {
+    let db = &state.db;
+
+    let user = require_user(&app_handle, db).await?;
+
+    let source = Path::new(&path);
+    if !source.is_file() {
+        return Err(AppError::NotFound(format!("File not found: {}", path)));
+    }
+
+    let title = if title.trim().is_empty() {
+        source
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or(path.clone())
+    } else {
+        title
+    };
+
+    let audio_id = Uuid::new_v4().to_string();
+    let data_path = get_data_path(&app_handle).unwrap_or("/data/".to_string());
+    let audio_dir = format!("{}/{}", data_path, audio_id);
+
+    tokio::fs::create_dir_all(&audio_dir).await?;
+
+    let imported = async {
+        let duration =
+            transcode_to_m4a(&app_handle, &path, &format!("{}/audio.m4a", audio_dir)).await?;
+
+        let end_time = i16::try_from(duration.ceil() as i64).map_err(|_| {
+            AppError::Validation(format!("Media is too long to import: {}s", duration))
+        })?;
+
+        create_audio(
+            db,
+            user.user_id.clone(),
+            audio_id.clone(),
+            title,
+            None,
+            path.clone(),
+            String::new(),
+            0,
+            end_time,
+            Provider::Local,
+            None,
+        )
+        .await
+        .map_err(AppError::from)
+    }
+    .await;
+
+    if let Err(e) = imported {
+        let _ = remove_dir_all_safe(&audio_dir).await;
+        return Err(e);
+    }
+
+    store_audio_content_hash(&app_handle, db, &audio_id).await;
+
+    Ok(get_audio(db, user.user_id, audio_id).await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_duration_from_ffmpeg_output() {
+        let stderr = "Input #0, mov,mp4,m4a,3gp,3g2,mj2, from 'lecture.mp4':
+  Metadata:
+    major_brand     : isom
+  Duration: 01:02:03.45, start: 0.000000, bitrate: 1205 kb/s
+  Stream #0:0[0x1](und): Video: h264 (High) (avc1 / 0x31637661)";
+
+        assert_eq!(parse_ffmpeg_duration(stderr), Some(3723.45));
+    }
+
+    #[test]
+    fn missing_duration_is_none() {
+        assert_eq!(parse_ffmpeg_duration("  Duration: N/A, bitrate: N/A"), None);
+        assert_eq!(parse_ffmpeg_duration("no input"), None);
+    }
+}
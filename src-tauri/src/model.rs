@@ -1,16 +1,34 @@
-use tauri::{AppHandle, Emitter};
-use tauri_plugin_shell::{process::CommandEvent, ShellExt};
-use tokio::fs::remove_file;
+use serde::{Deserialize, Serialize};
+use tauri::{async_runtime::JoinHandle, AppHandle, Emitter, Manager};
+use tokio::{
+    fs::remove_file,
+    sync::{watch, Semaphore},
+};
 
-use std::io::ErrorKind;
+use std::{collections::HashMap, io::ErrorKind, sync::Mutex};
 
 use crate::{
+    auth::{require_audio, require_user},
     config::{get_data_path, get_model_path},
+    db::Db,
+    error::AppError,
     query::{
-        audio::update_audio_initial_prompt, setting::get_app_settings,
-        user::get_user_by_session_token,
+        audio::{update_audio_initial_prompt, update_audio_language},
+        setting::get_app_settings,
+        transcript::replace_transcript,
+        transcription_job::{
+            cancel_transcription_job, create_transcription_job, fail_transcription_job,
+            finish_transcription_job, get_transcription_job, start_transcription_job,
+            update_transcription_progress, TranscriptionJob,
+        },
+    },
+    service::wx::{
+        TranscriptionComplete, TranscriptionProgress, TranscriptionResponse, WhisperXClient,
+    },
+    transcription::{
+        backend_for, parse_language, TranscriptionBackend, TranscriptionRequest,
+        TranscriptionUpdate, AUTO_LANGUAGE, DEFAULT_SERVICE_URL,
     },
-    service::wx::{TranscriptionComplete, TranscriptionProgress, WhisperXClient},
     DbState,
 };
 
@@ -22,299 +40,315 @@ async fn remove_file_safe(path: &str) -> tokio::io::Result<()> {
     }
 }
 
-#[tauri::command]
-#[specta::specta]
-pub async fn start_transcribe(
+#[derive(Debug, Default, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscribeOptions {
+    /// Defaults to the model selected in the settings
+    pub model: Option<String>,
+    /// A language code or `auto`, defaults to the audio's language and then
+    /// the app language
+    pub language: Option<String>,
+    pub initial_prompt: Option<String>,
+}
+
+// Transcriptions are heavy, the others wait in `queued`
+const MAX_CONCURRENT_TRANSCRIPTIONS: usize = 1;
+
+/// Background transcription tasks by audio id.
+pub struct TranscriptionJobs {
+    tasks: Mutex<HashMap<String, JoinHandle<()>>>,
+    slots: Semaphore,
+}
+
+impl Default for TranscriptionJobs {
+    fn default() -> Self {
+        Self {
+            tasks: Mutex::default(),
+            slots: Semaphore::new(MAX_CONCURRENT_TRANSCRIPTIONS),
+        }
+    }
+}
+
+impl TranscriptionJobs {
+    fn spawn(
+        &self,
+        app_handle: AppHandle,
+        audio_id: String,
+        request: TranscriptionRequest,
+        backend: Box<dyn TranscriptionBackend>,
+    ) {
+        // Held until the handle is stored, so a quick job can't try to remove itself first
+        let mut tasks = self.tasks.lock().unwrap();
+        let task = tauri::async_runtime::spawn(run_transcription_job(
+            app_handle,
+            audio_id.clone(),
+            request,
+            backend,
+        ));
+        tasks.insert(audio_id, task);
+    }
+
+    fn remove(&self, audio_id: &str) -> Option<JoinHandle<()>> {
+        self.tasks.lock().unwrap().remove(audio_id)
+    }
+}
+
+fn emit_transcription_error(app_handle: &AppHandle, audio_id: &str, status: &str, message: String) {
+    let error_event = TranscriptionProgress {
+        audio_id: audio_id.to_string(),
+        status: status.to_string(),
+        message,
+        progress: None,
+    };
+    let _ = app_handle.emit("transcription-error", &error_event);
+}
+
+/// Waits for a free slot, then transcribes while mirroring the progress into
+/// `transcription_job`.
+async fn transcribe_when_ready(
+    app_handle: &AppHandle,
+    db: &Db,
+    slots: &Semaphore,
+    audio_id: &str,
+    request: &TranscriptionRequest,
+    backend: &dyn TranscriptionBackend,
+) -> Result<Option<TranscriptionResponse>, String> {
+    let _slot = slots.acquire().await.map_err(|e| e.to_string())?;
+
+    if !start_transcription_job(db, audio_id)
+        .await
+        .map_err(|e| e.to_string())?
+    {
+        // Cancelled while queued
+        return Ok(None);
+    }
+
+    remove_file_safe(&format!("{}.json", request.output_path))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let (progress_tx, mut progress_rx) = watch::channel(None);
+
+    let on_progress = |update: TranscriptionUpdate| {
+        let _ = app_handle.emit(
+            "transcription-progress",
+            TranscriptionProgress {
+                audio_id: audio_id.to_string(),
+                status: update.status.clone(),
+                message: update.message.clone(),
+                progress: update.progress,
+            },
+        );
+        let _ = progress_tx.send(Some(update));
+    };
+
+    let transcription = backend.transcribe(app_handle, request, &on_progress);
+    tokio::pin!(transcription);
+
+    loop {
+        tokio::select! {
+            result = &mut transcription => return result.map(Some),
+            Ok(()) = progress_rx.changed() => {
+                let update = progress_rx.borrow_and_update().clone();
+                if let Some(update) = update {
+                    let _ = update_transcription_progress(db, audio_id, update.progress, &update.message).await;
+                }
+            }
+        }
+    }
+}
+
+async fn run_transcription_job(
     app_handle: AppHandle,
     audio_id: String,
-    model: String,
-) -> Result<(), String> {
-    let command = app_handle
-        .shell()
-        .sidecar("whip_v2")
-        .expect("whip_v2 sidecar does not exist");
-
-    // .sidecar("wpx")
-    // .expect("wpx sidecar does not exist");
-
-    let model_path = get_model_path(&app_handle).unwrap_or(format!("/"));
-
-    let data_path = get_data_path(&app_handle).unwrap_or(format!("/data/"));
-
-    let check_data = &format!("{}/{}/subtitle.json", data_path, audio_id);
-
-    remove_file_safe(check_data).await.unwrap();
-
-    let (mut rx, _child) = command
-        .args([
-            "--file",
-            &format!("{}/{}/audio.m4a", data_path, audio_id),
-            "--model",
-            &model,
-            "--model_path",
-            &format!("{}/models", model_path),
-            "--lang",
-            "en",
-            "--output",
-            &format!("{}/{}/subtitle", data_path, audio_id),
-        ])
-        .spawn()
-        .expect("spawn failed");
-
-    // TODO: emit message
-
-    let handle = tauri::async_runtime::spawn(async move {
-        while let Some(event) = rx.recv().await {
-            match event {
-                CommandEvent::Stdout(bytes) => {
-                    if let Ok(text) = String::from_utf8(bytes) {
-                        dbg!("Received from Stdout ", text);
-                    }
-                }
-                CommandEvent::Stderr(bytes) => {
-                    if let Ok(text) = String::from_utf8(bytes) {
-                        dbg!("Received from Stderr ", text);
-                    }
-                }
-                CommandEvent::Error(error) => {
-                    dbg!("Error:", error);
+    request: TranscriptionRequest,
+    backend: Box<dyn TranscriptionBackend>,
+) {
+    let state = app_handle.state::<DbState>();
+    let jobs = app_handle.state::<TranscriptionJobs>();
+    let db = &state.db;
+
+    let result = transcribe_when_ready(
+        &app_handle,
+        db,
+        &jobs.slots,
+        &audio_id,
+        &request,
+        backend.as_ref(),
+    )
+    .await;
+
+    jobs.remove(&audio_id);
+
+    match result {
+        Ok(Some(response)) => match finish_transcription_job(db, &audio_id).await {
+            Ok(true) => {
+                if let Err(e) = replace_transcript(db, &audio_id, &response.segments).await {
+                    println!("❌ Failed to store the transcript of {}: {}", audio_id, e);
                 }
-                CommandEvent::Terminated(payload) => {
-                    if payload.code != Some(0) {
-                        dbg!(format!("Process terminated with code: {:?}", payload.code));
+
+                if let Ok(Some(language)) = parse_language(&response.language) {
+                    if let Err(e) = update_audio_language(db, &audio_id, &language).await {
+                        println!("❌ Failed to update audio language: {}", e);
                     }
                 }
-                _ => {
-                    dbg!("Unkoown Error");
-                }
+
+                let completion_event = TranscriptionComplete {
+                    audio_id: audio_id.clone(),
+                    language: response.language,
+                    output_file: response.output_file,
+                    segments_count: response.segments.len(),
+                };
+                let _ = app_handle.emit("transcription-complete", &completion_event);
+            }
+            // Cancelled right as it finished
+            Ok(false) => {}
+            Err(e) => println!("❌ Failed to update transcription job {}: {}", audio_id, e),
+        },
+        Ok(None) => {}
+        Err(e) => {
+            if let Err(e) = fail_transcription_job(db, &audio_id, e.clone()).await {
+                println!("❌ Failed to update transcription job {}: {}", audio_id, e);
             }
+            emit_transcription_error(&app_handle, &audio_id, "error", e);
         }
-    });
-
-    let _ = handle.await.unwrap();
-    Ok(())
+    }
 }
 
+/// Queues a transcription of `data/<audio_id>/audio.m4a` into `subtitle.json`
+/// with the backend chosen in the settings and returns right away. Both
+/// backends emit the same `transcription-progress`, `transcription-complete`
+/// and `transcription-error` events, the job state is kept in `transcription_job`.
 #[tauri::command]
 #[specta::specta]
-pub async fn start_transcribe_service(
+pub async fn transcribe(
     app_handle: AppHandle,
+    state: tauri::State<'_, DbState>,
+    jobs: tauri::State<'_, TranscriptionJobs>,
     audio_id: String,
-    model: String,
-) -> Result<(), String> {
-    let model_path = get_model_path(&app_handle).unwrap_or(format!("/"));
-
-    let data_path = get_data_path(&app_handle).unwrap_or(format!("/data/"));
-
-    let check_data = &format!("{}/{}/subtitle.json", data_path, audio_id);
-
-    remove_file_safe(check_data).await.unwrap();
+    options: TranscribeOptions,
+) -> Result<TranscriptionJob, AppError> {
+    let db = &state.db;
 
-    let client = WhisperXClient::new("http://localhost:8081");
+    // Only the owner can transcribe an audio
+    let (user, audio) = require_audio(&app_handle, db, &audio_id).await?;
 
-    match client.health_check().await {
-        Ok(true) => println!("✅ Service is healthy"),
-        Ok(false) => println!("❌ Service is not healthy"),
-        Err(e) => println!("❌ Failed to check health: {}", e),
-    }
+    let settings = get_app_settings(db).await?;
 
-    match client
-        .transcribe(
-            &format!("{}/{}/audio.m4a", data_path, audio_id),
-            Some(&model), // model
-            Some("en"),   // language
-            Some(&format!("{}/models", model_path)),
-            Some(&format!("{}/{}/subtitle", data_path, audio_id)),
-            None, // initial_prompt not used in this function
+    let model = options.model.unwrap_or(settings.selected_model.clone());
+    let language = parse_language(
+        &options
+            .language
+            .or(audio.language)
+            .unwrap_or(settings.language.clone()),
+    )
+    .map_err(AppError::Validation)?;
+    let job_language = language.as_deref().unwrap_or(AUTO_LANGUAGE);
+
+    let job = create_transcription_job(db, &user.user_id, &audio_id, &model, job_language)
+        .await?
+        .ok_or(AppError::Validation(format!(
+            "Audio {} is already being transcribed",
+            audio_id
+        )))?;
+
+    if let Some(initial_prompt) = &options.initial_prompt {
+        if let Err(e) = update_audio_initial_prompt(
+            db,
+            user.user_id,
+            audio_id.clone(),
+            Some(initial_prompt.clone()),
         )
         .await
-    {
-        Ok(response) => {
-            println!("✅ Transcription completed!");
-            println!("🗣️  Language detected: {}", response.language);
-            println!("📄 Output file: {}", response.output_file);
-            println!("📝 Segments ({} total):", response.segments.len());
-
-            // Print first few segments
-            for (i, segment) in response.segments.iter().take(3).enumerate() {
-                println!(
-                    "  {}. [{:.2}s - {:.2}s]: {}",
-                    i + 1,
-                    segment.start,
-                    segment.end,
-                    segment.text.trim()
-                );
-            }
-
-            if response.segments.len() > 3 {
-                println!("  ... and {} more segments", response.segments.len() - 3);
-            }
-        }
-        Err(e) => {
-            println!("❌ Transcription failed: {}", e);
+        {
+            println!("Failed to update audio initial prompt: {}", e);
         }
     }
 
-    Ok(())
+    let model_path = get_model_path(&app_handle).unwrap_or("/".to_string());
+    let data_path = get_data_path(&app_handle).unwrap_or("/data/".to_string());
+
+    let request = TranscriptionRequest {
+        audio_path: format!("{}/{}/audio.m4a", data_path, audio_id),
+        output_path: format!("{}/{}/subtitle", data_path, audio_id),
+        model,
+        model_path: format!("{}/models", model_path),
+        language,
+        initial_prompt: options.initial_prompt.filter(|prompt| !prompt.is_empty()),
+    };
+
+    jobs.spawn(
+        app_handle.clone(),
+        audio_id,
+        request,
+        backend_for(&settings),
+    );
+
+    Ok(job)
 }
 
+/// The latest transcription job of the audio, `None` if it was never transcribed.
 #[tauri::command]
 #[specta::specta]
-pub async fn start_transcribe_service_streaming(
+pub async fn get_transcription_status(
     app_handle: AppHandle,
     state: tauri::State<'_, DbState>,
-    token: String,
     audio_id: String,
-    model: String,
-    initial_prompt: String,
-) -> Result<(), String> {
-    let model_path = get_model_path(&app_handle).unwrap_or(format!("/"));
-    let data_path = get_data_path(&app_handle).unwrap_or(format!("/data/"));
-    let check_data = &format!("{}/{}/subtitle.json", data_path, audio_id);
-    remove_file_safe(check_data).await.unwrap();
-
+) -> Result<Option<TranscriptionJob>, AppError> {
     let db = &state.db;
 
-    // Get the proxy URL from app_settings table
-    let proxy_url = match get_app_settings(db).await {
-        Ok(settings) => settings
-            .model_proxy
-            .unwrap_or_else(|| "http://localhost:8081".to_string()),
-        Err(_) => "http://localhost:8081".to_string(), // Fallback to default
-    };
+    let user = require_user(&app_handle, db).await?;
 
-    let client = WhisperXClient::new(&proxy_url);
-
-    // Health check
-    match client.health_check().await {
-        Ok(true) => println!("✅ Service is healthy"),
-        Ok(false) => {
-            println!("❌ Service is not healthy");
-            return Err("Service is not healthy".to_string());
-        }
-        Err(e) => {
-            println!("❌ Failed to check health: {}", e);
-            return Err(format!("Failed to check health: {}", e));
-        }
-    }
+    Ok(get_transcription_job(db, &user.user_id, &audio_id).await?)
+}
 
-    let user_info = get_user_by_session_token(db, &app_handle, token)
-        .await
-        .map_err(|e| format!("Failed to get user by session token: {}", e))?;
+#[tauri::command]
+#[specta::specta]
+pub async fn cancel_transcription(
+    app_handle: AppHandle,
+    state: tauri::State<'_, DbState>,
+    jobs: tauri::State<'_, TranscriptionJobs>,
+    audio_id: String,
+) -> Result<(), AppError> {
+    let db = &state.db;
 
-    if let Err(e) = update_audio_initial_prompt(
-        db,
-        user_info.unwrap().user_id,
-        audio_id.clone(),
-        Some(initial_prompt.clone()),
-    )
-    .await
-    {
-        println!("Failed to update audio initial prompt: {}", e);
-    }
+    let user = require_user(&app_handle, db).await?;
 
-    let audio_id_clone = audio_id.clone();
-    let app_handle_clone = app_handle.clone();
-
-    match client
-        .transcribe_streaming(
-            &format!("{}/{}/audio.m4a", data_path, audio_id),
-            Some(&model),
-            Some("en"),
-            Some(&format!("{}/models", model_path)),
-            Some(&format!("{}/{}/subtitle", data_path, audio_id)),
-            if initial_prompt.is_empty() {
-                None
-            } else {
-                Some(&initial_prompt)
-            },
-            |status_update| {
-                // Emit progress events to the frontend
-                let progress_event = TranscriptionProgress {
-                    audio_id: audio_id_clone.clone(),
-                    status: status_update.status.clone(),
-                    message: status_update.message.clone(),
-                    progress: status_update.progress,
-                };
+    let cancelled = cancel_transcription_job(db, &user.user_id, &audio_id).await?;
 
-                // Emit to frontend
-                app_handle_clone
-                    .emit("transcription-progress", &progress_event)
-                    .unwrap();
+    if !cancelled {
+        return Err(AppError::NotFound(format!(
+            "No transcription in progress for {}",
+            audio_id
+        )));
+    }
 
-                // Also log to console
-                println!("📊 [{}] {}", status_update.status, status_update.message);
-                if let Some(progress) = status_update.progress {
-                    println!("    Progress: {:.1}%", progress * 100.0);
-                }
-            },
-        )
-        .await
-    {
-        Ok(response) => {
-            println!("✅ Transcription completed!");
-            println!("🗣️  Language detected: {}", response.language);
-            println!("📄 Output file: {}", response.output_file);
-            println!("📝 Segments ({} total):", response.segments.len());
-
-            // Print first few segments
-            for (i, segment) in response.segments.iter().take(3).enumerate() {
-                println!(
-                    "  {}. [{:.2}s - {:.2}s]: {}",
-                    i + 1,
-                    segment.start,
-                    segment.end,
-                    segment.text.trim()
-                );
-            }
+    // Dropping the transcription kills the sidecar / closes the service request
+    if let Some(task) = jobs.remove(&audio_id) {
+        task.abort();
+    }
 
-            if response.segments.len() > 3 {
-                println!("  ... and {} more segments", response.segments.len() - 3);
-            }
+    emit_transcription_error(
+        &app_handle,
+        &audio_id,
+        "cancelled",
+        "Transcription cancelled".to_string(),
+    );
 
-            // Emit completion event to frontend
-            let completion_event = TranscriptionComplete {
-                audio_id: audio_id.clone(),
-                language: response.language,
-                output_file: response.output_file,
-                segments_count: response.segments.len(),
-            };
-            app_handle
-                .emit("transcription-complete", &completion_event)
-                .unwrap();
-
-            Ok(())
-        }
-        Err(e) => {
-            println!("❌ Transcription failed: {}", e);
-
-            // Emit error event to frontend
-            let error_event = TranscriptionProgress {
-                audio_id: audio_id.clone(),
-                status: "error".to_string(),
-                message: e.to_string(),
-                progress: None,
-            };
-            app_handle
-                .emit("transcription-error", &error_event)
-                .unwrap();
-
-            Err(format!("Transcription failed: {}", e))
-        }
-    }
+    Ok(())
 }
 
 #[tauri::command]
 #[specta::specta]
-pub async fn check_model_health(state: tauri::State<'_, DbState>) -> Result<bool, String> {
+pub async fn check_model_health(state: tauri::State<'_, DbState>) -> Result<bool, AppError> {
     let db = &state.db;
 
     // Get the proxy URL from app_settings table
     let proxy_url = match get_app_settings(db).await {
         Ok(settings) => settings
             .model_proxy
-            .unwrap_or_else(|| "http://localhost:8081".to_string()),
-        Err(_) => "http://localhost:8081".to_string(), // Fallback to default
+            .unwrap_or_else(|| DEFAULT_SERVICE_URL.to_string()),
+        Err(_) => DEFAULT_SERVICE_URL.to_string(), // Fallback to default
     };
 
     let client = WhisperXClient::new(&proxy_url);
@@ -330,7 +364,10 @@ pub async fn check_model_health(state: tauri::State<'_, DbState>) -> Result<bool
         }
         Err(e) => {
             println!("❌ Failed to check AI Model health: {}", e);
-            Err(format!("Failed to check AI Model health: {}", e))
+            Err(AppError::Backend(format!(
+                "Failed to check AI Model health: {}",
+                e
+            )))
         }
     }
 }
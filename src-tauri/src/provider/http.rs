@@ -0,0 +1,199 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use tauri::{AppHandle, Emitter, Manager, Url};
+use tauri_plugin_shell::{process::CommandEvent, ShellExt};
+
+use crate::{
+    config::get_data_path,
+    local_media::parse_ffmpeg_duration,
+    yt::{DownloadJobInfo, DownloadOutcome, DownloadState, DownloadStatus, VideoInfo},
+};
+
+use super::{MediaProvider, Provider};
+
+const AUDIO_EXTENSIONS: [&str; 9] = [
+    "mp3", "m4a", "aac", "wav", "ogg", "oga", "opus", "flac", "weba",
+];
+
+fn file_name(url: &Url) -> Option<&str> {
+    url.path_segments()?
+        .next_back()
+        .filter(|name| !name.is_empty())
+}
+
+pub fn is_audio_file_url(url: &Url) -> bool {
+    file_name(url)
+        .and_then(|name| name.rsplit_once('.'))
+        .is_some_and(|(_, extension)| AUDIO_EXTENSIONS.contains(&extension.to_lowercase().as_str()))
+}
+
+/// A plain link to an audio file, fetched and cut with ffmpeg.
+pub struct DirectHttp;
+
+#[async_trait]
+impl MediaProvider for DirectHttp {
+    fn kind(&self) -> Provider {
+        Provider::Http
+    }
+
+    async fn resolve(&self, app_handle: &AppHandle, url: &str) -> Result<VideoInfo, String> {
+        let parsed = Url::parse(url).map_err(|e| e.to_string())?;
+        let title = file_name(&parsed)
+            .map(|name| name.rsplit_once('.').map_or(name, |(stem, _)| stem))
+            .unwrap_or(url)
+            .replace("%20", " ");
+
+        // Without an output file ffmpeg only prints the input information (and exits with an error)
+        let output = app_handle
+            .shell()
+            .sidecar("ffmpeg")
+            .map_err(|e| format!("can't find ffmpeg sidecar: {}", e))?
+            .args(["-hide_banner", "-nostdin", "-i", url])
+            .output()
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let duration = parse_ffmpeg_duration(&String::from_utf8_lossy(&output.stderr));
+        if duration.is_none() && !output.status.success() {
+            return Err(format!("Failed to read audio from {}", url));
+        }
+
+        Ok(VideoInfo {
+            id: url.to_string(),
+            title,
+            description: None,
+            uploader: parsed.host_str().map(str::to_string),
+            duration,
+            thumbnail: None,
+            chapters: Vec::new(),
+            subtitle_languages: Vec::new(),
+            automatic_caption_languages: Vec::new(),
+        })
+    }
+
+    async fn download_section(
+        &self,
+        app_handle: &AppHandle,
+        job_id: &str,
+        url: &str,
+        start: i32,
+        end: i32,
+    ) -> Result<DownloadOutcome, String> {
+        let data_path = get_data_path(app_handle).unwrap_or("/data/".to_string());
+        let audio_dir = format!("{}/{}", data_path, job_id);
+
+        tokio::fs::create_dir_all(&audio_dir)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        app_handle
+            .emit(
+                "download_status",
+                DownloadStatus::Started {
+                    job_id: job_id.to_string(),
+                },
+            )
+            .map_err(|e| e.to_string())?;
+
+        let (mut rx, child) = app_handle
+            .shell()
+            .sidecar("ffmpeg")
+            .map_err(|e| format!("can't find ffmpeg sidecar: {}", e))?
+            .args([
+                "-hide_banner",
+                "-nostdin",
+                "-y",
+                "-ss",
+                &start.to_string(),
+                "-i",
+                url,
+                "-t",
+                &(end - start).to_string(),
+                "-vn",
+                "-c:a",
+                "aac",
+                "-b:a",
+                "192k",
+                &format!("{}/audio.m4a", audio_dir),
+            ])
+            .spawn()
+            .map_err(|e| e.to_string())?;
+
+        let download_state = app_handle.state::<DownloadState>();
+        download_state.insert(
+            DownloadJobInfo {
+                job_id: job_id.to_string(),
+                url: url.to_string(),
+                start,
+                end,
+                started_at: Utc::now().timestamp(),
+            },
+            child,
+        );
+
+        let mut last_line = String::new();
+        let mut result = Ok(());
+
+        while let Some(event) = rx.recv().await {
+            match event {
+                CommandEvent::Stderr(bytes) => {
+                    if let Some(line) = String::from_utf8_lossy(&bytes).lines().last() {
+                        last_line = line.to_string();
+                    }
+                }
+                CommandEvent::Error(error) => last_line = error,
+                CommandEvent::Terminated(payload) if payload.code != Some(0) => {
+                    result = Err(format!("Failed to download {}: {}", url, last_line));
+                }
+                _ => {}
+            }
+        }
+
+        // `cancel_download` takes the job out of the registry before killing it
+        if download_state.remove(job_id).is_none() {
+            return Ok(DownloadOutcome::Cancelled);
+        }
+
+        if let Err(message) = result {
+            let _ = app_handle.emit(
+                "download_status",
+                DownloadStatus::Error {
+                    message: message.clone(),
+                },
+            );
+            return Err(message);
+        }
+
+        app_handle
+            .emit("download_status", DownloadStatus::Finished)
+            .map_err(|e| e.to_string())?;
+
+        Ok(DownloadOutcome::Finished)
+    }
+
+    async fn thumbnail(
+        &self,
+        _app_handle: &AppHandle,
+        _url: &str,
+    ) -> Result<Option<String>, String> {
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_audio_file_urls() {
+        let url = |s: &str| Url::parse(s).unwrap();
+
+        assert!(is_audio_file_url(&url(
+            "https://cdn.example.com/show/ep-12.MP3?download=1"
+        )));
+        assert!(is_audio_file_url(&url("http://example.com/a/b/talk.opus")));
+        assert!(!is_audio_file_url(&url("https://example.com/watch/123")));
+        assert!(!is_audio_file_url(&url("https://example.com/video.mp4")));
+        assert!(!is_audio_file_url(&url("https://example.com/")));
+    }
+}
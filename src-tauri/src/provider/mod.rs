@@ -0,0 +1,163 @@
+use std::fmt;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Url};
+
+use crate::yt::{DownloadOutcome, VideoInfo};
+
+pub mod http;
+pub mod youtube;
+pub mod ytdlp;
+
+/// Where an audio item came from. Stored lowercase in `audio.provider`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "lowercase")]
+pub enum Provider {
+    // The frontend used to send the oEmbed `provider_name`
+    #[serde(alias = "YouTube")]
+    Youtube,
+    Ytdlp,
+    Http,
+    Local,
+}
+
+impl Provider {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Provider::Youtube => "youtube",
+            Provider::Ytdlp => "ytdlp",
+            Provider::Http => "http",
+            Provider::Local => "local",
+        }
+    }
+}
+
+impl fmt::Display for Provider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl TryFrom<String> for Provider {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.to_lowercase().as_str() {
+            "youtube" => Ok(Provider::Youtube),
+            "ytdlp" => Ok(Provider::Ytdlp),
+            "http" => Ok(Provider::Http),
+            "local" => Ok(Provider::Local),
+            _ => Err(format!("Unknown provider: {}", value)),
+        }
+    }
+}
+
+#[async_trait]
+pub trait MediaProvider: Send + Sync {
+    fn kind(&self) -> Provider;
+
+    /// Looks up title, duration, thumbnail etc. without downloading the media.
+    async fn resolve(&self, app_handle: &AppHandle, url: &str) -> Result<VideoInfo, String>;
+
+    /// Downloads the `start`-`end` section (in seconds) into `data/<job_id>/audio.m4a`.
+    async fn download_section(
+        &self,
+        app_handle: &AppHandle,
+        job_id: &str,
+        url: &str,
+        start: i32,
+        end: i32,
+    ) -> Result<DownloadOutcome, String>;
+
+    async fn thumbnail(&self, app_handle: &AppHandle, url: &str) -> Result<Option<String>, String>;
+}
+
+/// Picks the provider handling `url`: YouTube links, direct links to audio
+/// files, and everything else is left to yt-dlp's extractors.
+pub fn provider_for_url(url: &str) -> Result<Box<dyn MediaProvider>, String> {
+    let parsed = Url::parse(url).map_err(|e| format!("Invalid URL {}: {}", url, e))?;
+
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(format!("Unsupported URL: {}", url));
+    }
+
+    if youtube::is_youtube_url(&parsed) {
+        Ok(Box::new(youtube::YouTube))
+    } else if http::is_audio_file_url(&parsed) {
+        Ok(Box::new(http::DirectHttp))
+    } else {
+        Ok(Box::new(ytdlp::YtDlp))
+    }
+}
+
+/// Identifies the media behind `url`, so the different URL shapes of one
+/// YouTube video compare equal.
+pub fn media_key(url: &str) -> String {
+    if let Some(id) = youtube::video_id(url) {
+        return format!("youtube:{}", id);
+    }
+
+    match Url::parse(url.trim()) {
+        Ok(mut parsed) => {
+            parsed.set_fragment(None);
+            parsed.to_string()
+        }
+        Err(_) => url.trim().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kind(url: &str) -> Result<Provider, String> {
+        provider_for_url(url).map(|provider| provider.kind())
+    }
+
+    #[test]
+    fn selects_provider_by_url() {
+        assert_eq!(
+            kind("https://www.youtube.com/watch?v=dQw4w9WgXcQ"),
+            Ok(Provider::Youtube)
+        );
+        assert_eq!(kind("https://youtu.be/dQw4w9WgXcQ"), Ok(Provider::Youtube));
+        assert_eq!(
+            kind("https://cdn.example.com/episodes/42.mp3?token=abc"),
+            Ok(Provider::Http)
+        );
+        assert_eq!(kind("https://vimeo.com/76979871"), Ok(Provider::Ytdlp));
+        assert_eq!(
+            kind("https://soundcloud.com/artist/track"),
+            Ok(Provider::Ytdlp)
+        );
+        assert!(kind("file:///home/me/audio.mp3").is_err());
+        assert!(kind("not a url").is_err());
+    }
+
+    #[test]
+    fn parses_stored_provider_names() {
+        assert_eq!(
+            Provider::try_from("YouTube".to_string()),
+            Ok(Provider::Youtube)
+        );
+        assert_eq!(Provider::try_from("local".to_string()), Ok(Provider::Local));
+        assert!(Provider::try_from("".to_string()).is_err());
+    }
+
+    #[test]
+    fn media_key_ignores_url_shape() {
+        assert_eq!(
+            media_key("https://youtu.be/dQw4w9WgXcQ?t=10"),
+            media_key("https://www.youtube.com/watch?v=dQw4w9WgXcQ&list=PL123")
+        );
+        assert_eq!(
+            media_key("https://vimeo.com/76979871#t=30"),
+            media_key("https://vimeo.com/76979871")
+        );
+        assert_ne!(
+            media_key("https://www.youtube.com/watch?v=dQw4w9WgXcQ"),
+            media_key("https://www.youtube.com/watch?v=9bZkp7q19f0")
+        );
+    }
+}
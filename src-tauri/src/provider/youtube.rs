@@ -0,0 +1,111 @@
+use async_trait::async_trait;
+use tauri::{AppHandle, Url};
+
+use crate::yt::{dump_video_info, run_download, DownloadOutcome, VideoInfo};
+
+use super::{MediaProvider, Provider};
+
+const YOUTUBE_HOSTS: [&str; 6] = [
+    "youtube.com",
+    "www.youtube.com",
+    "m.youtube.com",
+    "music.youtube.com",
+    "www.youtube-nocookie.com",
+    "youtu.be",
+];
+
+pub fn is_youtube_url(url: &Url) -> bool {
+    url.host_str()
+        .is_some_and(|host| YOUTUBE_HOSTS.contains(&host))
+}
+
+/// Extracts the 11 character video id from the different YouTube URL shapes
+/// (`watch?v=`, `youtu.be/`, `/shorts/`, `/embed/`, `/live/`).
+pub fn video_id(url: &str) -> Option<String> {
+    let url = Url::parse(url).ok()?;
+    if !is_youtube_url(&url) {
+        return None;
+    }
+
+    let id = if url.host_str() == Some("youtu.be") {
+        url.path_segments()?.next().map(str::to_string)
+    } else if url.path() == "/watch" {
+        url.query_pairs()
+            .find(|(key, _)| key == "v")
+            .map(|(_, value)| value.into_owned())
+    } else {
+        let mut segments = url.path_segments()?;
+        match segments.next() {
+            Some("shorts" | "embed" | "live" | "v") => segments.next().map(str::to_string),
+            _ => None,
+        }
+    }?;
+
+    let valid = id.len() == 11
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+
+    valid.then_some(id)
+}
+
+pub struct YouTube;
+
+#[async_trait]
+impl MediaProvider for YouTube {
+    fn kind(&self) -> Provider {
+        Provider::Youtube
+    }
+
+    async fn resolve(&self, app_handle: &AppHandle, url: &str) -> Result<VideoInfo, String> {
+        dump_video_info(app_handle, url).await
+    }
+
+    async fn download_section(
+        &self,
+        app_handle: &AppHandle,
+        job_id: &str,
+        url: &str,
+        start: i32,
+        end: i32,
+    ) -> Result<DownloadOutcome, String> {
+        run_download(app_handle, job_id, url, "mp4", start, end).await
+    }
+
+    async fn thumbnail(
+        &self,
+        _app_handle: &AppHandle,
+        url: &str,
+    ) -> Result<Option<String>, String> {
+        Ok(video_id(url).map(|id| format!("https://i.ytimg.com/vi/{}/hqdefault.jpg", id)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_video_id() {
+        let urls = [
+            "https://www.youtube.com/watch?v=dQw4w9WgXcQ",
+            "https://www.youtube.com/watch?feature=share&v=dQw4w9WgXcQ&t=42s",
+            "https://m.youtube.com/watch?v=dQw4w9WgXcQ",
+            "https://youtu.be/dQw4w9WgXcQ?si=abcdef",
+            "https://www.youtube.com/shorts/dQw4w9WgXcQ",
+            "https://www.youtube.com/embed/dQw4w9WgXcQ",
+            "https://www.youtube.com/live/dQw4w9WgXcQ",
+        ];
+
+        for url in urls {
+            assert_eq!(video_id(url).as_deref(), Some("dQw4w9WgXcQ"), "{}", url);
+        }
+    }
+
+    #[test]
+    fn rejects_other_urls() {
+        assert_eq!(video_id("https://www.youtube.com/@channel"), None);
+        assert_eq!(video_id("https://www.youtube.com/watch?v=short"), None);
+        assert_eq!(video_id("https://vimeo.com/76979871"), None);
+    }
+}
@@ -0,0 +1,36 @@
+use async_trait::async_trait;
+use tauri::AppHandle;
+
+use crate::yt::{dump_video_info, run_download, DownloadOutcome, VideoInfo};
+
+use super::{MediaProvider, Provider};
+
+/// Any other site supported by yt-dlp (podcasts, Vimeo, SoundCloud, ...).
+pub struct YtDlp;
+
+#[async_trait]
+impl MediaProvider for YtDlp {
+    fn kind(&self) -> Provider {
+        Provider::Ytdlp
+    }
+
+    async fn resolve(&self, app_handle: &AppHandle, url: &str) -> Result<VideoInfo, String> {
+        dump_video_info(app_handle, url).await
+    }
+
+    async fn download_section(
+        &self,
+        app_handle: &AppHandle,
+        job_id: &str,
+        url: &str,
+        start: i32,
+        end: i32,
+    ) -> Result<DownloadOutcome, String> {
+        // Audio-only sites have no mp4 format to pick
+        run_download(app_handle, job_id, url, "bestaudio/best", start, end).await
+    }
+
+    async fn thumbnail(&self, app_handle: &AppHandle, url: &str) -> Result<Option<String>, String> {
+        Ok(dump_video_info(app_handle, url).await?.thumbnail)
+    }
+}
@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 use sqlx::prelude::FromRow;
 
-use crate::db::Db;
+use crate::{db::Db, provider::Provider};
 
 #[derive(Debug, Serialize, Deserialize, FromRow, specta::Type)]
 #[serde(rename_all = "camelCase")]
@@ -22,7 +22,8 @@ pub struct Audio {
     #[sqlx(rename = "endTime")]
     pub end_time: i16,
 
-    pub provider: String,
+    #[sqlx(try_from = "String")]
+    pub provider: Provider,
     pub tag: Option<String>,
 
     #[sqlx(rename = "lastUsedAt")]
@@ -47,11 +48,13 @@ pub struct AudioListItem {
     pub start_time: i16,
     #[sqlx(rename = "endTime")]
     pub end_time: i16,
-    pub provider: String,
+    #[sqlx(try_from = "String")]
+    pub provider: Provider,
     pub tag: Option<String>,
     pub transcribe: i16,
     #[sqlx(rename = "initialPrompt")]
     pub initial_prompt: Option<String>,
+    pub language: Option<String>,
     #[sqlx(rename = "updatedAt")]
     updated_at: String,
 }
@@ -70,7 +73,7 @@ pub async fn create_audio(
     thumbnail: String,
     start_time: i16,
     end_time: i16,
-    provider: String,
+    provider: Provider,
     _tag: Option<String>,
 ) -> Result<(), sqlx::Error> {
     sqlx::query(
@@ -101,7 +104,7 @@ pub async fn create_audio(
     .bind(thumbnail)
     .bind(start_time)
     .bind(end_time)
-    .bind(provider)
+    .bind(provider.as_str())
     .execute(db)
     .await?;
 
@@ -109,7 +112,7 @@ pub async fn create_audio(
 }
 
 pub async fn get_audios(db: &Db, user_id: String) -> Result<Vec<AudioListItem>, sqlx::Error> {
-    let audios = sqlx::query_as::<_, AudioListItem>("SELECT id, title, description, url, thumbnail, startTime, endTime, provider, tag, transcribe, initialPrompt, updatedAt FROM audio WHERE userId = ?  ORDER BY updatedAt DESC")
+    let audios = sqlx::query_as::<_, AudioListItem>("SELECT id, title, description, url, thumbnail, startTime, endTime, provider, tag, transcribe, initialPrompt, language, updatedAt FROM audio WHERE userId = ?  ORDER BY updatedAt DESC")
         .bind(&user_id)
         .fetch_all(db)
         .await?;
@@ -123,7 +126,7 @@ pub async fn get_audio(
     audio_id: String,
 ) -> Result<AudioItem, sqlx::Error> {
     let audio = sqlx::query_as::<_, AudioItem>(
-        "SELECT id, title, description, url, thumbnail, startTime, endTime, provider, tag, transcribe, initialPrompt, updatedAt FROM audio WHERE userId = ? AND id = ? ORDER BY updatedAt DESC"
+        "SELECT id, title, description, url, thumbnail, startTime, endTime, provider, tag, transcribe, initialPrompt, language, updatedAt FROM audio WHERE userId = ? AND id = ? ORDER BY updatedAt DESC"
     )
         .bind(&user_id)
         .bind(&audio_id)
@@ -180,6 +183,73 @@ pub async fn delete_audio(
     Ok(audios)
 }
 
+/// Audios of the user whose `startTime`-`endTime` range overlaps `start`-`end`,
+/// most overlapping first.
+pub async fn find_overlapping_audios(
+    db: &Db,
+    user_id: &str,
+    start: i32,
+    end: i32,
+) -> Result<Vec<AudioListItem>, sqlx::Error> {
+    sqlx::query_as::<_, AudioListItem>(
+        r#"
+        SELECT id, title, description, url, thumbnail, startTime, endTime, provider, tag, transcribe, initialPrompt, language, updatedAt
+        FROM audio
+        WHERE userId = ? AND startTime < ? AND endTime > ?
+        ORDER BY MIN(endTime, ?) - MAX(startTime, ?) DESC, updatedAt DESC
+        "#,
+    )
+    .bind(user_id)
+    .bind(end)
+    .bind(start)
+    .bind(end)
+    .bind(start)
+    .fetch_all(db)
+    .await
+}
+
+pub async fn find_audio_by_content_hash(
+    db: &Db,
+    user_id: &str,
+    content_hash: &str,
+) -> Result<Option<AudioListItem>, sqlx::Error> {
+    sqlx::query_as::<_, AudioListItem>(
+        "SELECT id, title, description, url, thumbnail, startTime, endTime, provider, tag, transcribe, initialPrompt, language, updatedAt FROM audio WHERE userId = ? AND contentHash = ? ORDER BY updatedAt DESC",
+    )
+    .bind(user_id)
+    .bind(content_hash)
+    .fetch_optional(db)
+    .await
+}
+
+pub async fn update_audio_content_hash(
+    db: &Db,
+    audio_id: &str,
+    content_hash: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE audio SET contentHash = ? WHERE id = ?")
+        .bind(content_hash)
+        .bind(audio_id)
+        .execute(db)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn update_audio_language(
+    db: &Db,
+    audio_id: &str,
+    language: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE audio SET language = ? WHERE id = ?")
+        .bind(language)
+        .bind(audio_id)
+        .execute(db)
+        .await?;
+
+    Ok(())
+}
+
 pub async fn update_audio_initial_prompt(
     db: &Db,
     user_id: String,
@@ -3,6 +3,8 @@ use sqlx::prelude::FromRow;
 
 use crate::db::Db;
 
+/// Practice state of one segment of an audio: whether it's bookmarked and
+/// dictated, and how the dictation attempts went.
 #[derive(Debug, Serialize, Deserialize, FromRow, specta::Type)]
 #[serde(rename_all = "camelCase")]
 pub struct BookmarkDictationView {
@@ -12,23 +14,30 @@ pub struct BookmarkDictationView {
     #[sqlx(rename = "audioId")]
     pub audio_id: String,
 
+    #[sqlx(rename = "segmentIndex")]
+    pub segment_index: i64,
+
+    /// `bookmark.id`, which is also the review card id
     #[sqlx(rename = "bookmark_id")]
     pub bookmark_id: Option<i64>,
 
-    #[sqlx(rename = "bookmarkId")]
-    pub bookmark_position: Option<i16>,
-
     #[sqlx(rename = "bookmark_created_at")]
     pub bookmark_created_at: Option<String>,
 
     #[sqlx(rename = "dictation_id")]
     pub dictation_id: Option<i64>,
 
-    #[sqlx(rename = "dictationId")]
-    pub dictation_position: Option<i16>,
-
     #[sqlx(rename = "dictation_created_at")]
     pub dictation_created_at: Option<String>,
+
+    #[sqlx(rename = "attempt_count")]
+    pub attempt_count: i64,
+
+    #[sqlx(rename = "latest_accuracy")]
+    pub latest_accuracy: Option<f64>,
+
+    #[sqlx(rename = "latest_attempt_at")]
+    pub latest_attempt_at: Option<String>,
 }
 
 pub async fn get_bookmark_dictation_combined(
@@ -37,7 +46,7 @@ pub async fn get_bookmark_dictation_combined(
     audio_id: String,
 ) -> Result<Vec<BookmarkDictationView>, sqlx::Error> {
     let combined_data = sqlx::query_as::<_, BookmarkDictationView>(
-        "SELECT * FROM bookmark_dictation_view WHERE userId = ? AND audioId = ? ORDER BY COALESCE(bookmark_created_at, dictation_created_at) ASC"
+        "SELECT * FROM bookmark_dictation_view WHERE userId = ? AND audioId = ? ORDER BY segmentIndex ASC"
     )
     .bind(&user_id)
     .bind(&audio_id)
@@ -46,3 +55,88 @@ pub async fn get_bookmark_dictation_combined(
 
     Ok(combined_data)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_db;
+
+    async fn seed(db: &Db) {
+        sqlx::query(
+            r#"
+            INSERT INTO user (id, name) VALUES ('u1', 'one'), ('u2', 'two');
+            INSERT INTO audio (id, userId, title, url, startTime, endTime, provider)
+            VALUES ('a1', 'u1', 'first', 'https://example.com/1', 0, 60, 'http'),
+                ('a2', 'u1', 'second', 'https://example.com/2', 0, 60, 'http'),
+                ('b1', 'u2', 'other', 'https://example.com/3', 0, 60, 'http');
+
+            INSERT INTO bookmark (userId, audioId, bookmarkId) VALUES
+                ('u1', 'a1', 1), ('u1', 'a1', 3), ('u1', 'a2', 0), ('u2', 'b1', 1);
+            INSERT INTO dictation (userId, audioId, dictationId) VALUES
+                ('u1', 'a1', 1), ('u1', 'a1', 2), ('u1', 'a1', 5), ('u2', 'b1', 1);
+            INSERT INTO dictation_attempt (userId, audioId, segmentIndex, expectedText, typedText, marks, accuracy)
+            VALUES ('u1', 'a1', 1, 'a b', 'a', '[]', 0.5),
+                ('u1', 'a1', 1, 'a b', 'a b', '[]', 1.0),
+                ('u1', 'a1', 4, 'c', 'd', '[]', 0.0);
+            "#,
+        )
+        .execute(db)
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn one_row_per_segment() {
+        let db = test_db().await;
+        seed(&db).await;
+
+        let rows = get_bookmark_dictation_combined(&db, "u1".to_string(), "a1".to_string())
+            .await
+            .unwrap();
+
+        let shape: Vec<_> = rows
+            .iter()
+            .map(|row| {
+                (
+                    row.segment_index,
+                    row.bookmark_id.is_some(),
+                    row.dictation_id.is_some(),
+                    row.attempt_count,
+                    row.latest_accuracy,
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            shape,
+            vec![
+                (1, true, true, 2, Some(1.0)),
+                (2, false, true, 0, None),
+                (3, true, false, 0, None),
+                (4, false, false, 1, Some(0.0)),
+                (5, false, true, 0, None),
+            ]
+        );
+        assert!(rows
+            .iter()
+            .all(|row| row.user_id == "u1" && row.audio_id == "a1"));
+    }
+
+    #[tokio::test]
+    async fn rows_stay_within_the_audio() {
+        let db = test_db().await;
+        seed(&db).await;
+
+        let rows = get_bookmark_dictation_combined(&db, "u1".to_string(), "a2".to_string())
+            .await
+            .unwrap();
+
+        assert_eq!(rows.len(), 1);
+        assert_eq!((rows[0].segment_index, rows[0].dictation_id), (0, None));
+
+        let other_user = get_bookmark_dictation_combined(&db, "u1".to_string(), "b1".to_string())
+            .await
+            .unwrap();
+        assert!(other_user.is_empty());
+    }
+}
@@ -1,30 +1,38 @@
 use crate::{
+    auth::{current_user, require_audio, require_user, unbind_session},
     config::get_data_path,
+    db::Db,
+    error::AppError,
+    provider::Provider,
     query::{
         audio::{AudioItem, AudioListItem},
         bookmark_dictation::BookmarkDictationView,
         setting::{AppSettings, UpdateSettingsRequest},
     },
+    transcript::import_subtitle_file,
     DbState,
 };
+use sha2::{Digest, Sha256};
 use std::io::ErrorKind;
 use tauri::AppHandle;
 use tokio::fs::remove_dir_all;
 
 use super::{
-    audio::{create_audio, delete_audio, get_audio, get_audios, update_audio_transcribe},
+    audio::{
+        create_audio, delete_audio, get_audio, get_audios, update_audio_content_hash,
+        update_audio_transcribe,
+    },
     bookmark::{create_bookmark_item, delete_bookmark_item},
     bookmark_dictation::get_bookmark_dictation_combined,
     dictation::{create_dictation_item, delete_dictation_item},
-    oauth::handle_google_auth,
     setting::{get_or_create_settings, update_app_settings},
-    store::{delete_store_token, get_store_token, set_store_token},
     user::{
-        delete_session, get_user_by_session_token, update_user_name, SessionWithUser, Timestamp,
+        delete_session, delete_user_session, delete_user_sessions, get_user_sessions,
+        update_user_name, CurrentUser, SessionInfo,
     },
 };
 
-async fn remove_dir_all_safe(path: &str) -> tokio::io::Result<()> {
+pub(crate) async fn remove_dir_all_safe(path: &str) -> tokio::io::Result<()> {
     match remove_dir_all(path).await {
         Ok(()) => Ok(()),
         Err(e) if e.kind() == ErrorKind::NotFound => Ok(()), // File doesn't exist, that's fine
@@ -32,95 +40,120 @@ async fn remove_dir_all_safe(path: &str) -> tokio::io::Result<()> {
     }
 }
 
-#[derive(serde::Deserialize, specta::Type)]
-pub struct TokenData {
-    pub access_token: Option<String>,
-    pub access_token_expires_at: Option<Timestamp>,
-    pub refresh_token: Option<String>,
-    pub refresh_token_expires_at: Option<Timestamp>,
+/// sha256 of `data/<audio_id>/audio.m4a`, `None` if it can't be read.
+pub(crate) async fn audio_content_hash(app_handle: &AppHandle, audio_id: &str) -> Option<String> {
+    let data_path = get_data_path(app_handle).ok()?;
+    let bytes = tokio::fs::read(format!("{}/{}/audio.m4a", data_path, audio_id))
+        .await
+        .ok()?;
+
+    Some(format!("{:x}", Sha256::digest(&bytes)))
+}
+
+/// Remembers the content hash of a newly created audio for duplicate detection.
+pub(crate) async fn store_audio_content_hash(app_handle: &AppHandle, db: &Db, audio_id: &str) {
+    if let Some(hash) = audio_content_hash(app_handle, audio_id).await {
+        if let Err(e) = update_audio_content_hash(db, audio_id, &hash).await {
+            println!("❌ Failed to store content hash of {}: {}", audio_id, e);
+        }
+    }
 }
 
 #[tauri::command]
 #[specta::specta]
-pub async fn handle_login(
+pub async fn check_persist_user(
     app_handle: AppHandle,
     state: tauri::State<'_, DbState>,
-    sub: String,
-    email: String,
-    name: String,
-    picture: Option<String>,
-    email_verified: bool,
-    tokens: TokenData,
-) -> Result<String, String> {
+) -> Result<Option<CurrentUser>, AppError> {
     let db = &state.db;
 
-    let session_token = handle_google_auth(
-        &db,
-        sub,
-        email,
-        name,
-        picture,
-        email_verified,
-        tokens.access_token,
-        tokens.access_token_expires_at,
-        tokens.refresh_token,
-        tokens.refresh_token_expires_at,
-    )
-    .await
-    .map_err(|e| e.to_string());
+    Ok(current_user(&app_handle, db).await?.map(CurrentUser::from))
+}
 
-    match session_token {
-        Ok(token) => {
-            let _ = set_store_token(app_handle, &token).expect("Failed to store session token");
-            Ok(token)
-        }
-        Err(er) => Err(er),
+#[tauri::command]
+#[specta::specta]
+pub async fn logout_user(
+    app_handle: AppHandle,
+    state: tauri::State<'_, DbState>,
+) -> Result<(), AppError> {
+    let db = &state.db;
+    let session_token = unbind_session(&app_handle)?;
+
+    if let Some(token) = session_token {
+        delete_session(db, token).await?;
     }
+
+    Ok(())
 }
 
+/// Signs the user out of every session, on this and other devices.
 #[tauri::command]
 #[specta::specta]
-pub async fn check_persist_user(
+pub async fn logout_all_sessions(
     app_handle: AppHandle,
     state: tauri::State<'_, DbState>,
-) -> Result<Option<SessionWithUser>, String> {
+) -> Result<(), AppError> {
     let db = &state.db;
-    let session_token = get_store_token(&app_handle)?;
 
-    let user_info = get_user_by_session_token(db, &app_handle, session_token)
-        .await
-        .expect("Failed to get user");
+    let user = require_user(&app_handle, db).await?;
+
+    delete_user_sessions(db, &user.user_id).await?;
+    unbind_session(&app_handle)?;
 
-    Ok(user_info)
+    Ok(())
 }
 
+/// The live sessions of the signed in user, on this and other devices.
 #[tauri::command]
 #[specta::specta]
-pub async fn logout_user(
+pub async fn list_sessions(
     app_handle: AppHandle,
     state: tauri::State<'_, DbState>,
-) -> Result<(), String> {
+) -> Result<Vec<SessionInfo>, AppError> {
     let db = &state.db;
-    let session_token = delete_store_token(&app_handle)?;
 
-    if let Some(token) = session_token {
-        let _ = delete_session(db, token);
+    let user = require_user(&app_handle, db).await?;
+
+    Ok(get_user_sessions(db, &user.user_id, &user.access_token).await?)
+}
+
+/// Signs a session out, revoking the current one signs this app out too.
+#[tauri::command]
+#[specta::specta]
+pub async fn revoke_session(
+    app_handle: AppHandle,
+    state: tauri::State<'_, DbState>,
+    session_id: String,
+) -> Result<Vec<SessionInfo>, AppError> {
+    let db = &state.db;
+
+    let user = require_user(&app_handle, db).await?;
+
+    let token = delete_user_session(db, &user.user_id, &session_id)
+        .await?
+        .ok_or(AppError::NotFound(format!(
+            "Session {} does not exist",
+            session_id
+        )))?;
+
+    if token == user.access_token {
+        unbind_session(&app_handle)?;
+        return Ok(Vec::new());
     }
 
-    Ok(())
+    Ok(get_user_sessions(db, &user.user_id, &user.access_token).await?)
 }
 
 #[derive(serde::Deserialize, specta::Type)]
 pub struct CreateAudioData {
     pub audio_id: String,
-    pub token: String,
     pub title: String,
     pub description: Option<String>,
     pub url: String,
     pub thumbnail: String,
     pub start_time: i16,
     pub end_time: i16,
-    pub provider: String,
+    pub provider: Provider,
     pub tag: Option<String>,
 }
 
@@ -130,33 +163,28 @@ pub async fn handle_create_audio(
     app_handle: AppHandle,
     state: tauri::State<'_, DbState>,
     audio_data: CreateAudioData,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let db = &state.db;
 
-    let user_info = get_user_by_session_token(db, &app_handle, audio_data.token)
-        .await
-        .expect("create audio failed: invalid user");
-
-    if let Some(user) = user_info {
-        create_audio(
-            db,
-            user.user_id,
-            audio_data.audio_id,
-            audio_data.title,
-            audio_data.description,
-            audio_data.url,
-            audio_data.thumbnail,
-            audio_data.start_time,
-            audio_data.end_time,
-            audio_data.provider,
-            audio_data.tag,
-        )
-        .await
-        .expect("create audio failed: invalid paramsters");
-        return Ok(());
-    } else {
-        return Err("Failed to create auido".to_string());
-    }
+    let user = require_user(&app_handle, db).await?;
+
+    create_audio(
+        db,
+        user.user_id,
+        audio_data.audio_id.clone(),
+        audio_data.title,
+        audio_data.description,
+        audio_data.url,
+        audio_data.thumbnail,
+        audio_data.start_time,
+        audio_data.end_time,
+        audio_data.provider,
+        audio_data.tag,
+    )
+    .await?;
+    store_audio_content_hash(&app_handle, db, &audio_data.audio_id).await;
+
+    Ok(())
 }
 
 #[tauri::command]
@@ -164,22 +192,12 @@ pub async fn handle_create_audio(
 pub async fn handle_get_audio_list(
     app_handle: AppHandle,
     state: tauri::State<'_, DbState>,
-    token: String,
-) -> Result<Vec<AudioListItem>, String> {
+) -> Result<Vec<AudioListItem>, AppError> {
     let db = &state.db;
 
-    let user_info = get_user_by_session_token(db, &app_handle, token)
-        .await
-        .expect("get audio list failed: invalid user");
-
-    if let Some(user) = user_info {
-        let audio_list = get_audios(db, user.user_id)
-            .await
-            .expect("get audio list failed: invalid paramsters");
-        return Ok(audio_list);
-    } else {
-        return Err("Failed to get auido list".to_string());
-    }
+    let user = require_user(&app_handle, db).await?;
+
+    Ok(get_audios(db, user.user_id).await?)
 }
 
 #[tauri::command]
@@ -187,23 +205,13 @@ pub async fn handle_get_audio_list(
 pub async fn handle_get_audio_item(
     app_handle: AppHandle,
     state: tauri::State<'_, DbState>,
-    token: String,
     audio_id: String,
-) -> Result<AudioItem, String> {
+) -> Result<AudioItem, AppError> {
     let db = &state.db;
 
-    let user_info = get_user_by_session_token(db, &app_handle, token)
-        .await
-        .expect("get audio item failed: invalid user");
-
-    if let Some(user) = user_info {
-        let audio_item = get_audio(db, user.user_id, audio_id)
-            .await
-            .expect("get audio item failed: invalid paramsters");
-        return Ok(audio_item);
-    } else {
-        return Err("Failed to get auido item".to_string());
-    }
+    let user = require_user(&app_handle, db).await?;
+
+    Ok(get_audio(db, user.user_id, audio_id).await?)
 }
 
 #[tauri::command]
@@ -211,23 +219,19 @@ pub async fn handle_get_audio_item(
 pub async fn handle_update_audio_transcribe(
     app_handle: AppHandle,
     state: tauri::State<'_, DbState>,
-    token: String,
     audio_id: String,
-) -> Result<AudioItem, String> {
+) -> Result<AudioItem, AppError> {
     let db = &state.db;
 
-    let user_info = get_user_by_session_token(db, &app_handle, token)
-        .await
-        .expect("update audio item failed: invalid user");
-
-    if let Some(user) = user_info {
-        let audio_item = update_audio_transcribe(db, user.user_id, audio_id)
-            .await
-            .expect("update audio item failed: invalid paramsters");
-        return Ok(audio_item);
-    } else {
-        return Err("Failed to update auido item".to_string());
+    let user = require_user(&app_handle, db).await?;
+
+    let audio_item = update_audio_transcribe(db, user.user_id, audio_id.clone()).await?;
+
+    if let Err(e) = import_subtitle_file(&app_handle, db, &audio_id).await {
+        println!("❌ Failed to import the subtitles of {}: {}", audio_id, e);
     }
+
+    Ok(audio_item)
 }
 
 #[tauri::command]
@@ -235,27 +239,19 @@ pub async fn handle_update_audio_transcribe(
 pub async fn handle_delete_audio(
     app_handle: AppHandle,
     state: tauri::State<'_, DbState>,
-    token: String,
     audio_id: String,
-) -> Result<Vec<AudioListItem>, String> {
+) -> Result<Vec<AudioListItem>, AppError> {
     let db = &state.db;
 
-    let user_info = get_user_by_session_token(db, &app_handle, token)
-        .await
-        .expect("delete audio item failed: invalid user");
-
-    if let Some(user) = user_info {
-        let data_path = get_data_path(&app_handle).unwrap_or(format!("/data/"));
-        let check_dir = &format!("{}/{}", data_path, audio_id);
-        remove_dir_all_safe(check_dir).await.unwrap();
-
-        let audio_list = delete_audio(db, user.user_id, audio_id)
-            .await
-            .expect("delete audio failed: invalid paramsters");
-        return Ok(audio_list);
-    } else {
-        return Err("Failed to delete auido item".to_string());
-    }
+    // Only an audio of the user, so the id is a known directory under data/
+    let (user, audio) = require_audio(&app_handle, db, &audio_id).await?;
+    let data_path = get_data_path(&app_handle).map_err(AppError::Io)?;
+
+    let audios = delete_audio(db, user.user_id, audio.id.clone()).await?;
+
+    remove_dir_all_safe(&format!("{}/{}", data_path, audio.id)).await?;
+
+    Ok(audios)
 }
 
 #[tauri::command]
@@ -263,28 +259,16 @@ pub async fn handle_delete_audio(
 pub async fn handle_create_bookmark_item(
     app_handle: AppHandle,
     state: tauri::State<'_, DbState>,
-    token: String,
     audio_id: String,
     bookmark_id: i16,
-) -> Result<Vec<BookmarkDictationView>, String> {
+) -> Result<Vec<BookmarkDictationView>, AppError> {
     let db = &state.db;
 
-    let user_info = get_user_by_session_token(db, &app_handle, token)
-        .await
-        .expect("create bookmark item failed: invalid user");
-
-    if let Some(user) = user_info {
-        let _ = create_bookmark_item(db, user.user_id.clone(), audio_id.clone(), bookmark_id)
-            .await
-            .expect("create bookmark item failed: invalid paramsters");
-
-        let combined_list = get_bookmark_dictation_combined(db, user.user_id, audio_id)
-            .await
-            .expect("get bookmark dictation combined failed: invalid paramsters");
-        return Ok(combined_list);
-    } else {
-        return Err("Failed to create bookmark item".to_string());
-    }
+    let user = require_user(&app_handle, db).await?;
+
+    create_bookmark_item(db, user.user_id.clone(), audio_id.clone(), bookmark_id).await?;
+
+    Ok(get_bookmark_dictation_combined(db, user.user_id, audio_id).await?)
 }
 
 #[tauri::command]
@@ -292,28 +276,16 @@ pub async fn handle_create_bookmark_item(
 pub async fn handle_delete_bookmark_item(
     app_handle: AppHandle,
     state: tauri::State<'_, DbState>,
-    token: String,
     audio_id: String,
     bookmark_id: i16,
-) -> Result<Vec<BookmarkDictationView>, String> {
+) -> Result<Vec<BookmarkDictationView>, AppError> {
     let db = &state.db;
 
-    let user_info = get_user_by_session_token(db, &app_handle, token)
-        .await
-        .expect("delete bookmark item failed: invalid user");
-
-    if let Some(user) = user_info {
-        let _ = delete_bookmark_item(db, user.user_id.clone(), audio_id.clone(), bookmark_id)
-            .await
-            .expect("delete bookmark item failed: invalid paramsters");
-
-        let combined_list = get_bookmark_dictation_combined(db, user.user_id, audio_id)
-            .await
-            .expect("get bookmark dictation combined failed: invalid paramsters");
-        return Ok(combined_list);
-    } else {
-        return Err("Failed to delete bookmark item".to_string());
-    }
+    let user = require_user(&app_handle, db).await?;
+
+    delete_bookmark_item(db, user.user_id.clone(), audio_id.clone(), bookmark_id).await?;
+
+    Ok(get_bookmark_dictation_combined(db, user.user_id, audio_id).await?)
 }
 
 #[tauri::command]
@@ -321,28 +293,16 @@ pub async fn handle_delete_bookmark_item(
 pub async fn handle_create_dictation_item(
     app_handle: AppHandle,
     state: tauri::State<'_, DbState>,
-    token: String,
     audio_id: String,
     dictation_id: i16,
-) -> Result<Vec<BookmarkDictationView>, String> {
+) -> Result<Vec<BookmarkDictationView>, AppError> {
     let db = &state.db;
 
-    let user_info = get_user_by_session_token(db, &app_handle, token)
-        .await
-        .expect("create dictation item failed: invalid user");
-
-    if let Some(user) = user_info {
-        let _ = create_dictation_item(db, user.user_id.clone(), audio_id.clone(), dictation_id)
-            .await
-            .expect("create dictation item failed: invalid paramsters");
-
-        let combined_list = get_bookmark_dictation_combined(db, user.user_id, audio_id)
-            .await
-            .expect("get bookmark dictation combined failed: invalid paramsters");
-        return Ok(combined_list);
-    } else {
-        return Err("Failed to create dictation item".to_string());
-    }
+    let user = require_user(&app_handle, db).await?;
+
+    create_dictation_item(db, user.user_id.clone(), audio_id.clone(), dictation_id).await?;
+
+    Ok(get_bookmark_dictation_combined(db, user.user_id, audio_id).await?)
 }
 
 #[tauri::command]
@@ -350,28 +310,16 @@ pub async fn handle_create_dictation_item(
 pub async fn handle_delete_dictation_item(
     app_handle: AppHandle,
     state: tauri::State<'_, DbState>,
-    token: String,
     audio_id: String,
     dictation_id: i16,
-) -> Result<Vec<BookmarkDictationView>, String> {
+) -> Result<Vec<BookmarkDictationView>, AppError> {
     let db = &state.db;
 
-    let user_info = get_user_by_session_token(db, &app_handle, token)
-        .await
-        .expect("delete dictation item failed: invalid user");
-
-    if let Some(user) = user_info {
-        let _ = delete_dictation_item(db, user.user_id.clone(), audio_id.clone(), dictation_id)
-            .await
-            .expect("delete dictation item failed: invalid paramsters");
-
-        let combined_list = get_bookmark_dictation_combined(db, user.user_id, audio_id)
-            .await
-            .expect("get bookmark dictation combined failed: invalid paramsters");
-        return Ok(combined_list);
-    } else {
-        return Err("Failed to delete dictation item".to_string());
-    }
+    let user = require_user(&app_handle, db).await?;
+
+    delete_dictation_item(db, user.user_id.clone(), audio_id.clone(), dictation_id).await?;
+
+    Ok(get_bookmark_dictation_combined(db, user.user_id, audio_id).await?)
 }
 
 #[tauri::command]
@@ -379,23 +327,13 @@ pub async fn handle_delete_dictation_item(
 pub async fn handle_get_bookmark_dictation_combined(
     app_handle: AppHandle,
     state: tauri::State<'_, DbState>,
-    token: String,
     audio_id: String,
-) -> Result<Vec<BookmarkDictationView>, String> {
+) -> Result<Vec<BookmarkDictationView>, AppError> {
     let db = &state.db;
 
-    let user_info = get_user_by_session_token(db, &app_handle, token)
-        .await
-        .expect("get bookmark dictation combined failed: invalid user");
-
-    if let Some(user) = user_info {
-        let combined_list = get_bookmark_dictation_combined(db, user.user_id, audio_id)
-            .await
-            .expect("get bookmark dictation combined failed: invalid paramsters");
-        return Ok(combined_list);
-    } else {
-        return Err("Failed to get bookmark dictation combined".to_string());
-    }
+    let user = require_user(&app_handle, db).await?;
+
+    Ok(get_bookmark_dictation_combined(db, user.user_id, audio_id).await?)
 }
 
 #[tauri::command]
@@ -403,22 +341,12 @@ pub async fn handle_get_bookmark_dictation_combined(
 pub async fn handle_get_app_settings(
     app_handle: AppHandle,
     state: tauri::State<'_, DbState>,
-    token: String,
-) -> Result<AppSettings, String> {
+) -> Result<AppSettings, AppError> {
     let db = &state.db;
 
-    let user_info = get_user_by_session_token(db, &app_handle, token)
-        .await
-        .expect("get app settings failed: invalid user");
+    require_user(&app_handle, db).await?;
 
-    if let Some(_user) = user_info {
-        match get_or_create_settings(db).await {
-            Ok(settings) => Ok(settings),
-            Err(e) => Err(format!("Failed to get app settings: {}", e)),
-        }
-    } else {
-        return Err("Failed to get app settings".to_string());
-    }
+    Ok(get_or_create_settings(db).await?)
 }
 
 #[tauri::command]
@@ -426,23 +354,13 @@ pub async fn handle_get_app_settings(
 pub async fn handle_update_app_settings(
     app_handle: AppHandle,
     state: tauri::State<'_, DbState>,
-    token: String,
     request: UpdateSettingsRequest,
-) -> Result<AppSettings, String> {
+) -> Result<AppSettings, AppError> {
     let db = &state.db;
 
-    let user_info = get_user_by_session_token(db, &app_handle, token)
-        .await
-        .expect("update app settings failed: invalid user");
+    require_user(&app_handle, db).await?;
 
-    if let Some(_user) = user_info {
-        match update_app_settings(db, request).await {
-            Ok(settings) => Ok(settings),
-            Err(e) => Err(format!("Failed to update app settings: {}", e)),
-        }
-    } else {
-        return Err("Failed to update app settings".to_string());
-    }
+    Ok(update_app_settings(db, request).await?)
 }
 
 #[tauri::command]
@@ -450,21 +368,13 @@ pub async fn handle_update_app_settings(
 pub async fn handle_update_user_name(
     app_handle: AppHandle,
     state: tauri::State<'_, DbState>,
-    token: String,
     new_name: String,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let db = &state.db;
 
-    let user_info = get_user_by_session_token(db, &app_handle, token)
-        .await
-        .expect("update user name failed: invalid user");
+    let user = require_user(&app_handle, db).await?;
 
-    if let Some(user) = user_info {
-        match update_user_name(db, &user.user_id, new_name).await {
-            Ok(_) => Ok(()),
-            Err(e) => Err(format!("Failed to update user name: {}", e)),
-        }
-    } else {
-        return Err("Failed to update user name".to_string());
-    }
+    update_user_name(db, &user.user_id, new_name).await?;
+
+    Ok(())
 }
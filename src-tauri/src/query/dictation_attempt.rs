@@ -0,0 +1,82 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{prelude::FromRow, types::Json};
+
+use crate::{
+    db::Db,
+    dictation::{DictationScore, WordMark},
+};
+
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DictationAttempt {
+    pub id: i64,
+
+    #[sqlx(rename = "audioId")]
+    pub audio_id: String,
+
+    #[sqlx(rename = "segmentIndex")]
+    pub segment_index: i64,
+
+    #[sqlx(rename = "expectedText")]
+    pub expected_text: String,
+
+    #[sqlx(rename = "typedText")]
+    pub typed_text: String,
+
+    #[sqlx(json)]
+    pub marks: Vec<WordMark>,
+
+    pub accuracy: f64,
+
+    #[sqlx(rename = "createdAt")]
+    pub created_at: String,
+}
+
+pub async fn create_dictation_attempt(
+    db: &Db,
+    user_id: &str,
+    audio_id: &str,
+    segment_index: i64,
+    expected_text: &str,
+    typed_text: &str,
+    score: &DictationScore,
+) -> Result<DictationAttempt, sqlx::Error> {
+    sqlx::query_as::<_, DictationAttempt>(
+        r#"
+        INSERT INTO dictation_attempt (userId, audioId, segmentIndex, expectedText, typedText, marks, accuracy, createdAt)
+        VALUES (?, ?, ?, ?, ?, ?, ?, CURRENT_TIMESTAMP)
+        RETURNING id, audioId, segmentIndex, expectedText, typedText, marks, accuracy, createdAt
+        "#,
+    )
+    .bind(user_id)
+    .bind(audio_id)
+    .bind(segment_index)
+    .bind(expected_text)
+    .bind(typed_text)
+    .bind(Json(&score.marks))
+    .bind(score.accuracy)
+    .fetch_one(db)
+    .await
+}
+
+pub async fn get_dictation_attempts(
+    db: &Db,
+    user_id: &str,
+    audio_id: &str,
+    segment_index: Option<i64>,
+) -> Result<Vec<DictationAttempt>, sqlx::Error> {
+    sqlx::query_as::<_, DictationAttempt>(
+        r#"
+        SELECT id, audioId, segmentIndex, expectedText, typedText, marks, accuracy, createdAt
+        FROM dictation_attempt
+        WHERE userId = ? AND audioId = ? AND (? IS NULL OR segmentIndex = ?)
+        ORDER BY id DESC
+        "#,
+    )
+    .bind(user_id)
+    .bind(audio_id)
+    .bind(segment_index)
+    .bind(segment_index)
+    .fetch_all(db)
+    .await
+}
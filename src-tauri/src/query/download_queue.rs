@@ -0,0 +1,204 @@
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+
+use crate::{db::Db, provider::Provider};
+
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadQueueItem {
+    pub id: String,
+
+    #[sqlx(rename = "userId")]
+    pub user_id: String,
+
+    pub url: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub thumbnail: Option<String>,
+
+    #[sqlx(rename = "startTime")]
+    pub start_time: i16,
+
+    #[sqlx(rename = "endTime")]
+    pub end_time: i16,
+
+    #[sqlx(try_from = "String")]
+    pub provider: Provider,
+    pub tag: Option<String>,
+    pub status: String,
+    pub attempts: i64,
+    pub error: Option<String>,
+
+    #[sqlx(rename = "nextAttemptAt")]
+    pub next_attempt_at: i64,
+
+    #[sqlx(rename = "createdAt")]
+    created_at: String,
+
+    #[sqlx(rename = "updatedAt")]
+    updated_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct EnqueueDownloadRequest {
+    pub url: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub thumbnail: Option<String>,
+    pub start_time: i16,
+    pub end_time: i16,
+    pub tag: Option<String>,
+}
+
+pub async fn enqueue_download(
+    db: &Db,
+    user_id: String,
+    id: String,
+    provider: Provider,
+    request: EnqueueDownloadRequest,
+) -> Result<DownloadQueueItem, sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO download_queue (
+            id,
+            userId,
+            url,
+            title,
+            description,
+            thumbnail,
+            startTime,
+            endTime,
+            provider,
+            tag
+        ) VALUES (
+            ?, ?, ?, ?, ?, ?, ?, ?, ?, ?
+        )
+        "#,
+    )
+    .bind(&id)
+    .bind(&user_id)
+    .bind(request.url)
+    .bind(request.title)
+    .bind(request.description)
+    .bind(request.thumbnail)
+    .bind(request.start_time)
+    .bind(request.end_time)
+    .bind(provider.as_str())
+    .bind(request.tag)
+    .execute(db)
+    .await?;
+
+    get_download_queue_item(db, &id).await
+}
+
+pub async fn get_download_queue_item(db: &Db, id: &str) -> Result<DownloadQueueItem, sqlx::Error> {
+    sqlx::query_as::<_, DownloadQueueItem>("SELECT * FROM download_queue WHERE id = ?")
+        .bind(id)
+        .fetch_one(db)
+        .await
+}
+
+pub async fn get_download_queue(
+    db: &Db,
+    user_id: String,
+) -> Result<Vec<DownloadQueueItem>, sqlx::Error> {
+    sqlx::query_as::<_, DownloadQueueItem>(
+        "SELECT * FROM download_queue WHERE userId = ? ORDER BY createdAt ASC",
+    )
+    .bind(&user_id)
+    .fetch_all(db)
+    .await
+}
+
+/// Puts jobs that were running when the app last exited back in the queue.
+pub async fn requeue_running_downloads(db: &Db) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE download_queue SET status = 'queued', updatedAt = CURRENT_TIMESTAMP WHERE status = 'running'",
+    )
+    .execute(db)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Marks the oldest due job as running and returns it.
+pub async fn claim_next_download(db: &Db) -> Result<Option<DownloadQueueItem>, sqlx::Error> {
+    sqlx::query_as::<_, DownloadQueueItem>(
+        r#"
+        UPDATE download_queue
+        SET status = 'running', attempts = attempts + 1, updatedAt = CURRENT_TIMESTAMP
+        WHERE id = (
+            SELECT id FROM download_queue
+            WHERE status = 'queued' AND nextAttemptAt <= unixepoch()
+            ORDER BY createdAt ASC
+            LIMIT 1
+        )
+        RETURNING *
+        "#,
+    )
+    .fetch_optional(db)
+    .await
+}
+
+/// Marks a running job as done. Returns `false` if it was cancelled in the meantime.
+pub async fn finish_download(db: &Db, id: &str) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE download_queue SET status = 'done', error = NULL, updatedAt = CURRENT_TIMESTAMP WHERE id = ? AND status = 'running'",
+    )
+    .bind(id)
+    .execute(db)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn fail_download(db: &Db, id: &str, error: String) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE download_queue SET status = 'failed', error = ?, updatedAt = CURRENT_TIMESTAMP WHERE id = ?",
+    )
+    .bind(error)
+    .bind(id)
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn retry_download_later(
+    db: &Db,
+    id: &str,
+    error: String,
+    delay_secs: i64,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE download_queue
+        SET status = 'queued', error = ?, nextAttemptAt = unixepoch() + ?, updatedAt = CURRENT_TIMESTAMP
+        WHERE id = ?
+        "#,
+    )
+    .bind(error)
+    .bind(delay_secs)
+    .bind(id)
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+/// Cancels a job that is queued or running. Returns `false` if there was none.
+pub async fn cancel_queued_download(db: &Db, id: &str) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        r#"
+        UPDATE download_queue
+        SET status = 'cancelled', updatedAt = CURRENT_TIMESTAMP
+        WHERE id = ? AND status IN ('queued', 'running')
+        "#,
+    )
+    .bind(id)
+    .execute(db)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
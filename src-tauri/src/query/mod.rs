@@ -3,7 +3,13 @@ pub mod bookmark;
 pub mod bookmark_dictation;
 pub mod commands;
 pub mod dictation;
+pub mod dictation_attempt;
+pub mod download_queue;
 pub mod oauth;
+pub mod review;
 pub mod setting;
+pub mod stats;
 pub mod store;
+pub mod transcript;
+pub mod transcription_job;
 pub mod user;
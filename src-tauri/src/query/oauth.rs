@@ -91,9 +91,12 @@ pub async fn handle_google_auth(
             user_id
         }
         None => {
-            let user_id = create_user(db, name, email, email_verified, picture).await?;
-            let _ = create_account(
-                db,
+            // Together, a failed account insert would leave a user whose email
+            // can't sign up again
+            let mut tx = db.begin().await?;
+            let user_id = create_user(&mut *tx, name, email, email_verified, picture).await?;
+            create_account(
+                &mut *tx,
                 user_id.clone(),
                 sub,
                 String::from("google"),
@@ -102,14 +105,16 @@ pub async fn handle_google_auth(
                 refresh_token,
                 refresh_token_expires_at,
             )
-            .await;
+            .await?;
+            tx.commit().await?;
             user_id
         }
     };
 
-    let user = get_user_by_id(db, &user_id).await?;
-    let user = user.expect("Edge case");
-    let user_id = user.id;
+    let user_id = get_user_by_id(db, &user_id)
+        .await?
+        .ok_or(sqlx::Error::RowNotFound)?
+        .id;
 
     let session_token = create_session(db, user_id).await?;
 
@@ -0,0 +1,113 @@
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+
+use crate::{db::Db, review::ReviewSchedule};
+
+/// A bookmarked segment as a review card, `id` is the `bookmark.id`.
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ReviewCard {
+    pub id: i64,
+
+    #[sqlx(rename = "audioId")]
+    pub audio_id: String,
+
+    #[sqlx(rename = "audioTitle")]
+    pub audio_title: String,
+
+    #[sqlx(rename = "bookmarkId")]
+    pub bookmark_id: i64,
+
+    /// `None` while the audio has no transcript
+    pub text: Option<String>,
+
+    #[sqlx(rename = "startTime")]
+    pub start_time: Option<f64>,
+
+    #[sqlx(rename = "endTime")]
+    pub end_time: Option<f64>,
+
+    #[sqlx(rename = "easeFactor")]
+    pub ease_factor: f64,
+
+    #[sqlx(rename = "intervalDays")]
+    pub interval_days: i64,
+
+    pub repetitions: i64,
+
+    /// `None` for cards that were never reviewed
+    #[sqlx(rename = "dueAt")]
+    pub due_at: Option<String>,
+
+    #[sqlx(rename = "lastReviewedAt")]
+    pub last_reviewed_at: Option<String>,
+}
+
+const REVIEW_CARD_COLUMNS: &str = r#"
+    b.id, b.audioId, a.title AS audioTitle, b.bookmarkId, s.text, s.startTime, s.endTime,
+    b.easeFactor, b.intervalDays, b.repetitions, b.dueAt, b.lastReviewedAt
+    FROM bookmark b
+    JOIN audio a ON a.id = b.audioId
+    LEFT JOIN transcript_segment s ON s.audioId = b.audioId AND s.segmentIndex = b.bookmarkId
+"#;
+
+/// Cards of all audios of the user that are due, the longest overdue first.
+pub async fn get_due_reviews(
+    db: &Db,
+    user_id: &str,
+    limit: i64,
+) -> Result<Vec<ReviewCard>, sqlx::Error> {
+    sqlx::query_as::<_, ReviewCard>(&format!(
+        r#"
+        SELECT {REVIEW_CARD_COLUMNS}
+        WHERE b.userId = ? AND (b.dueAt IS NULL OR b.dueAt <= CURRENT_TIMESTAMP)
+        ORDER BY COALESCE(b.dueAt, b.createdAt) ASC, b.id ASC
+        LIMIT ?
+        "#
+    ))
+    .bind(user_id)
+    .bind(limit)
+    .fetch_all(db)
+    .await
+}
+
+pub async fn get_review_card(
+    db: &Db,
+    user_id: &str,
+    card_id: i64,
+) -> Result<Option<ReviewCard>, sqlx::Error> {
+    sqlx::query_as::<_, ReviewCard>(&format!(
+        "SELECT {REVIEW_CARD_COLUMNS} WHERE b.userId = ? AND b.id = ?"
+    ))
+    .bind(user_id)
+    .bind(card_id)
+    .fetch_optional(db)
+    .await
+}
+
+/// Stores the new schedule, the card is due `interval_days` from now.
+pub async fn update_review_schedule(
+    db: &Db,
+    user_id: &str,
+    card_id: i64,
+    schedule: &ReviewSchedule,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE bookmark
+        SET easeFactor = ?, intervalDays = ?, repetitions = ?,
+            dueAt = datetime('now', ?), lastReviewedAt = CURRENT_TIMESTAMP
+        WHERE userId = ? AND id = ?
+        "#,
+    )
+    .bind(schedule.ease_factor)
+    .bind(schedule.interval_days)
+    .bind(schedule.repetitions)
+    .bind(format!("+{} days", schedule.interval_days))
+    .bind(user_id)
+    .bind(card_id)
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
@@ -1,7 +1,10 @@
 use serde::{Deserialize, Serialize};
 use sqlx::prelude::FromRow;
 
-use crate::db::Db;
+use crate::{db::Db, transcription::TranscriptionBackendKind};
+
+/// Shorter sessions would sign the user out while they practice.
+const MIN_SESSION_SECONDS: i64 = 300;
 
 #[derive(Debug, Serialize, Deserialize, FromRow, specta::Type)]
 #[serde(rename_all = "camelCase")]
@@ -19,6 +22,14 @@ pub struct AppSettings {
     pub last_login: Option<String>,
     #[sqlx(rename = "autoLogin")]
     pub auto_login: bool,
+    #[sqlx(rename = "maxConcurrentDownloads")]
+    pub max_concurrent_downloads: i64,
+    #[sqlx(rename = "transcriptionBackend", try_from = "String")]
+    pub transcription_backend: TranscriptionBackendKind,
+    #[sqlx(rename = "sessionIdleSeconds")]
+    pub session_idle_seconds: i64,
+    #[sqlx(rename = "sessionAbsoluteSeconds")]
+    pub session_absolute_seconds: i64,
 }
 
 #[derive(Debug, Serialize, Deserialize, specta::Type)]
@@ -29,11 +40,17 @@ pub struct UpdateSettingsRequest {
     pub selected_model: Option<String>,
     pub model_proxy: Option<String>,
     pub auto_login: Option<bool>,
+    pub max_concurrent_downloads: Option<i64>,
+    pub transcription_backend: Option<TranscriptionBackendKind>,
+    /// Sign out after this long without using the app
+    pub session_idle_seconds: Option<i64>,
+    /// Sign out this long after signing in
+    pub session_absolute_seconds: Option<i64>,
 }
 
 pub async fn get_app_settings(db: &Db) -> Result<AppSettings, sqlx::Error> {
     let settings = sqlx::query_as::<_, AppSettings>(
-        "SELECT id, currentUserId, theme, language, selectedModel, modelProxy, lastLogin, autoLogin FROM app_settings LIMIT 1"
+        "SELECT id, currentUserId, theme, language, selectedModel, modelProxy, lastLogin, autoLogin, maxConcurrentDownloads, transcriptionBackend, sessionIdleSeconds, sessionAbsoluteSeconds FROM app_settings LIMIT 1"
     )
     .fetch_one(db)
     .await?;
@@ -48,6 +65,16 @@ pub async fn update_app_settings(
     let mut query_parts = Vec::new();
     let mut bind_values = Vec::new();
 
+    let max_concurrent_downloads = request
+        .max_concurrent_downloads
+        .map(|max| max.max(1).to_string());
+    let session_idle_seconds = request
+        .session_idle_seconds
+        .map(|seconds| seconds.max(MIN_SESSION_SECONDS).to_string());
+    let session_absolute_seconds = request
+        .session_absolute_seconds
+        .map(|seconds| seconds.max(MIN_SESSION_SECONDS).to_string());
+
     if let Some(theme) = &request.theme {
         query_parts.push("theme = ?");
         bind_values.push(theme.as_str());
@@ -73,6 +100,26 @@ pub async fn update_app_settings(
         bind_values.push(if auto_login { "1" } else { "0" });
     }
 
+    if let Some(max_concurrent_downloads) = &max_concurrent_downloads {
+        query_parts.push("maxConcurrentDownloads = ?");
+        bind_values.push(max_concurrent_downloads.as_str());
+    }
+
+    if let Some(transcription_backend) = request.transcription_backend {
+        query_parts.push("transcriptionBackend = ?");
+        bind_values.push(transcription_backend.as_str());
+    }
+
+    if let Some(session_idle_seconds) = &session_idle_seconds {
+        query_parts.push("sessionIdleSeconds = ?");
+        bind_values.push(session_idle_seconds.as_str());
+    }
+
+    if let Some(session_absolute_seconds) = &session_absolute_seconds {
+        query_parts.push("sessionAbsoluteSeconds = ?");
+        bind_values.push(session_absolute_seconds.as_str());
+    }
+
     if query_parts.is_empty() {
         return get_app_settings(db).await;
     }
@@ -113,4 +160,4 @@ pub async fn get_or_create_settings(db: &Db) -> Result<AppSettings, sqlx::Error>
         Err(sqlx::Error::RowNotFound) => create_default_settings(db).await,
         Err(e) => Err(e),
     }
-}
\ No newline at end of file
+}
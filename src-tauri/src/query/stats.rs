@@ -0,0 +1,260 @@
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+
+use crate::db::Db;
+
+/// An attempt counts the time since the user's previous attempt, a longer
+/// pause than this starts a new session.
+const SESSION_GAP_SECONDS: i64 = 300;
+/// What the first attempt of a session counts, listening and typing one segment.
+const SESSION_START_SECONDS: i64 = 60;
+
+/// Attempts with the local day they were made on and the seconds of practice they count for.
+const ATTEMPTS: &str = r#"
+    WITH gaps AS (
+        SELECT audioId, segmentIndex, accuracy, createdAt,
+            unixepoch(createdAt) - unixepoch(LAG(createdAt) OVER (ORDER BY createdAt, id)) AS gap
+        FROM dictation_attempt
+        WHERE userId = ?1
+    ),
+    attempts AS (
+        SELECT audioId, segmentIndex, accuracy, createdAt,
+            date(createdAt, 'localtime') AS day,
+            CASE WHEN gap IS NULL OR gap > ?3 THEN ?4 ELSE gap END AS seconds
+        FROM gaps
+    )
+"#;
+
+#[derive(Debug, Default, Serialize, Deserialize, FromRow, Clone, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct AttemptStats {
+    /// First day of the period, `None` for the totals
+    pub period: Option<String>,
+    pub attempts: i64,
+    #[sqlx(rename = "segmentsDictated")]
+    pub segments_dictated: i64,
+    #[sqlx(rename = "averageAccuracy")]
+    pub average_accuracy: Option<f64>,
+    #[sqlx(rename = "minutesPracticed")]
+    pub minutes_practiced: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct MissedWord {
+    pub word: String,
+    pub count: i64,
+}
+
+/// Attempt stats since `since` (a `YYYY-MM-DD` local date, `None` for all
+/// time), one row per `period` (an SQL expression over `day`), or a single
+/// totals row when `period` is `None`.
+pub async fn get_attempt_stats(
+    db: &Db,
+    user_id: &str,
+    since: Option<&str>,
+    period: Option<&str>,
+) -> Result<Vec<AttemptStats>, sqlx::Error> {
+    let (select, group) = match period {
+        Some(period) => (period, format!("GROUP BY {period} ORDER BY {period}")),
+        None => ("NULL", String::new()),
+    };
+
+    sqlx::query_as::<_, AttemptStats>(&format!(
+        r#"
+        {ATTEMPTS}
+        SELECT {select} AS period,
+            COUNT(*) AS attempts,
+            COUNT(DISTINCT audioId || ':' || segmentIndex) AS segmentsDictated,
+            AVG(accuracy) AS averageAccuracy,
+            COALESCE(SUM(seconds), 0) / 60.0 AS minutesPracticed
+        FROM attempts
+        WHERE ?2 IS NULL OR day >= ?2
+        {group}
+        "#
+    ))
+    .bind(user_id)
+    .bind(since)
+    .bind(SESSION_GAP_SECONDS)
+    .bind(SESSION_START_SECONDS)
+    .fetch_all(db)
+    .await
+}
+
+/// Rows of `table` (bookmark or audio) created per `period`, an SQL expression over `day`.
+pub async fn get_created_counts(
+    db: &Db,
+    table: &str,
+    user_id: &str,
+    since: Option<&str>,
+    period: &str,
+) -> Result<Vec<(String, i64)>, sqlx::Error> {
+    sqlx::query_as(&format!(
+        r#"
+        WITH created AS (
+            SELECT date(createdAt, 'localtime') AS day FROM {table} WHERE userId = ?1
+        )
+        SELECT {period} AS period, COUNT(*)
+        FROM created
+        WHERE ?2 IS NULL OR day >= ?2
+        GROUP BY {period}
+        "#
+    ))
+    .bind(user_id)
+    .bind(since)
+    .fetch_all(db)
+    .await
+}
+
+/// Transcript words the user missed or misspelled most often.
+pub async fn get_most_missed_words(
+    db: &Db,
+    user_id: &str,
+    since: Option<&str>,
+    limit: i64,
+) -> Result<Vec<MissedWord>, sqlx::Error> {
+    sqlx::query_as::<_, MissedWord>(
+        r#"
+        SELECT json_extract(mark.value, '$.expected') AS word, COUNT(*) AS count
+        FROM dictation_attempt a, json_each(a.marks) mark
+        WHERE a.userId = ?1
+            AND (?2 IS NULL OR date(a.createdAt, 'localtime') >= ?2)
+            AND json_extract(mark.value, '$.kind') IN ('missing', 'misspelled')
+        GROUP BY word
+        ORDER BY count DESC, word ASC
+        LIMIT ?3
+        "#,
+    )
+    .bind(user_id)
+    .bind(since)
+    .bind(limit)
+    .fetch_all(db)
+    .await
+}
+
+/// Local days with any practice (dictations, attempts or bookmarks), ascending.
+pub async fn get_practice_days(db: &Db, user_id: &str) -> Result<Vec<String>, sqlx::Error> {
+    sqlx::query_scalar(
+        r#"
+        SELECT date(createdAt, 'localtime') AS day FROM dictation_attempt WHERE userId = ?1
+        UNION
+        SELECT date(createdAt, 'localtime') FROM dictation WHERE userId = ?1
+        UNION
+        SELECT date(createdAt, 'localtime') FROM bookmark WHERE userId = ?1
+        ORDER BY day ASC
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(db)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{db::test_db, stats::StatsRange};
+
+    // At midday UTC, so the local day is the same in most time zones
+    async fn seed(db: &Db) {
+        sqlx::query(
+            r#"
+            INSERT INTO user (id, name) VALUES ('u1', 'one'), ('u2', 'two');
+            INSERT INTO audio (id, userId, title, url, startTime, endTime, provider)
+            VALUES ('a1', 'u1', 'first', 'https://example.com/1', 0, 60, 'http'),
+                ('a2', 'u1', 'second', 'https://example.com/2', 0, 60, 'http'),
+                ('b1', 'u2', 'other', 'https://example.com/3', 0, 60, 'http');
+
+            INSERT INTO dictation_attempt (userId, audioId, segmentIndex, expectedText, typedText, marks, accuracy, createdAt)
+            VALUES
+                -- Wednesday: two attempts 2 minutes apart, then one after a long pause
+                ('u1', 'a1', 0, 'the fox', 'the', '[{"kind":"correct","expected":"the","typed":"the"},{"kind":"missing","expected":"fox","typed":null}]', 0.5, '2026-10-14 12:00:00'),
+                ('u1', 'a1', 0, 'the fox', 'the fox', '[]', 1.0, '2026-10-14 12:02:00'),
+                ('u1', 'a1', 1, 'fox dog', 'fax', '[{"kind":"misspelled","expected":"fox","typed":"fax"},{"kind":"missing","expected":"dog","typed":null}]', 0.0, '2026-10-14 12:30:00'),
+                -- Sunday, still the week of Monday the 12th
+                ('u1', 'a2', 0, 'fox', '', '[{"kind":"missing","expected":"fox","typed":null}]', 0.75, '2026-10-18 12:00:00'),
+                -- Monday, the next week
+                ('u1', 'a2', 0, 'fox', 'fox', '[]', 0.25, '2026-10-19 12:00:00'),
+                ('u2', 'b1', 0, 'fox', '', '[{"kind":"missing","expected":"fox","typed":null}]', 0.0, '2026-10-14 12:01:00');
+            "#,
+        )
+        .execute(db)
+        .await
+        .unwrap();
+    }
+
+    /// Period, attempts, segments, average accuracy and minutes
+    type Shape<'a> = (Option<&'a str>, i64, i64, Option<f64>, f64);
+
+    fn shape(stats: &[AttemptStats]) -> Vec<Shape<'_>> {
+        stats
+            .iter()
+            .map(|stats| {
+                (
+                    stats.period.as_deref(),
+                    stats.attempts,
+                    stats.segments_dictated,
+                    stats.average_accuracy,
+                    stats.minutes_practiced,
+                )
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn groups_attempts_by_day_and_week() {
+        let db = test_db().await;
+        seed(&db).await;
+
+        // The first attempt of a session counts 1 minute, the next one the 2 minutes since it
+        let days = get_attempt_stats(&db, "u1", None, Some(StatsRange::Month.period()))
+            .await
+            .unwrap();
+        assert_eq!(
+            shape(&days),
+            vec![
+                (Some("2026-10-14"), 3, 2, Some(0.5), 4.0),
+                (Some("2026-10-18"), 1, 1, Some(0.75), 1.0),
+                (Some("2026-10-19"), 1, 1, Some(0.25), 1.0),
+            ]
+        );
+
+        let weeks = get_attempt_stats(&db, "u1", None, Some(StatsRange::All.period()))
+            .await
+            .unwrap();
+        assert_eq!(
+            shape(&weeks),
+            vec![
+                (Some("2026-10-12"), 4, 3, Some(0.5625), 5.0),
+                (Some("2026-10-19"), 1, 1, Some(0.25), 1.0),
+            ]
+        );
+
+        let totals = get_attempt_stats(&db, "u1", None, None).await.unwrap();
+        assert_eq!(shape(&totals), vec![(None, 5, 3, Some(0.5), 6.0)]);
+
+        let recent = get_attempt_stats(&db, "u1", Some("2026-10-18"), None)
+            .await
+            .unwrap();
+        assert_eq!(shape(&recent), vec![(None, 2, 1, Some(0.5), 2.0)]);
+    }
+
+    #[tokio::test]
+    async fn counts_missed_and_misspelled_words() {
+        let db = test_db().await;
+        seed(&db).await;
+
+        let words: Vec<_> = get_most_missed_words(&db, "u1", None, 10)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|word| (word.word, word.count))
+            .collect();
+        assert_eq!(words, vec![("fox".to_string(), 3), ("dog".to_string(), 1)]);
+
+        let recent = get_most_missed_words(&db, "u1", Some("2026-10-18"), 10)
+            .await
+            .unwrap();
+        assert_eq!(recent.len(), 1);
+        assert_eq!((recent[0].word.as_str(), recent[0].count), ("fox", 1));
+    }
+}
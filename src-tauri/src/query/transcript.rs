@@ -0,0 +1,642 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+use sqlx::{prelude::FromRow, types::Json, SqliteConnection};
+
+use crate::{
+    db::Db,
+    service::wx::{Segment, Word},
+};
+
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptSegment {
+    pub id: i64,
+
+    #[sqlx(rename = "audioId")]
+    pub audio_id: String,
+
+    #[sqlx(rename = "segmentIndex")]
+    pub segment_index: i64,
+
+    #[sqlx(rename = "startTime")]
+    pub start_time: f64,
+
+    #[sqlx(rename = "endTime")]
+    pub end_time: f64,
+
+    pub text: String,
+
+    #[sqlx(json(nullable))]
+    pub words: Option<Vec<Word>>,
+}
+
+impl From<TranscriptSegment> for Segment {
+    fn from(segment: TranscriptSegment) -> Self {
+        Segment {
+            start: segment.start_time,
+            end: segment.end_time,
+            text: segment.text,
+            words: segment.words,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptRevision {
+    pub id: i64,
+
+    #[sqlx(rename = "audioId")]
+    pub audio_id: String,
+
+    #[sqlx(rename = "userId")]
+    pub user_id: String,
+
+    pub action: String,
+
+    #[sqlx(rename = "segmentIndex")]
+    pub segment_index: i64,
+
+    #[sqlx(rename = "oldSegments", json)]
+    pub old_segments: Vec<Segment>,
+
+    #[sqlx(rename = "newSegments", json)]
+    pub new_segments: Vec<Segment>,
+
+    pub undone: bool,
+
+    #[sqlx(rename = "createdAt")]
+    pub created_at: String,
+}
+
+/// Replaces the transcript of the audio with `segments` and marks it transcribed.
+pub async fn replace_transcript(
+    db: &Db,
+    audio_id: &str,
+    segments: &[Segment],
+) -> Result<(), sqlx::Error> {
+    let mut tx = db.begin().await?;
+
+    sqlx::query("DELETE FROM transcript_segment WHERE audioId = ?")
+        .bind(audio_id)
+        .execute(&mut *tx)
+        .await?;
+
+    // Revisions splice segments by index and can't be undone on a new transcript
+    sqlx::query("DELETE FROM transcript_revision WHERE audioId = ?")
+        .bind(audio_id)
+        .execute(&mut *tx)
+        .await?;
+
+    for (index, segment) in segments.iter().enumerate() {
+        sqlx::query(
+            r#"
+            INSERT INTO transcript_segment (audioId, segmentIndex, startTime, endTime, text, words)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(audio_id)
+        .bind(index as i64)
+        .bind(segment.start)
+        .bind(segment.end)
+        .bind(&segment.text)
+        .bind(segment.words.as_ref().map(Json))
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    sqlx::query("UPDATE audio SET transcribe = 1 WHERE id = ?")
+        .bind(audio_id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await
+}
+
+pub async fn get_transcript(
+    db: &Db,
+    user_id: &str,
+    audio_id: &str,
+) -> Result<Vec<TranscriptSegment>, sqlx::Error> {
+    sqlx::query_as::<_, TranscriptSegment>(
+        r#"
+        SELECT s.id, s.audioId, s.segmentIndex, s.startTime, s.endTime, s.text, s.words
+        FROM transcript_segment s
+        JOIN audio a ON a.id = s.audioId
+        WHERE a.userId = ? AND s.audioId = ?
+        ORDER BY s.segmentIndex ASC
+        "#,
+    )
+    .bind(user_id)
+    .bind(audio_id)
+    .fetch_all(db)
+    .await
+}
+
+/// Ids of the audios that have no transcript rows yet.
+pub async fn get_audios_without_transcript(db: &Db) -> Result<Vec<String>, sqlx::Error> {
+    sqlx::query_scalar(
+        r#"
+        SELECT a.id FROM audio a
+        WHERE NOT EXISTS (SELECT 1 FROM transcript_segment s WHERE s.audioId = a.id)
+        "#,
+    )
+    .fetch_all(db)
+    .await
+}
+
+/// Where a bookmark or dictation pointing at segment `reference` ends up after
+/// `old_len` segments starting at `index` were replaced by `new_len` segments.
+/// References inside the replaced range stay at their offset, clamped to the
+/// new range.
+pub(crate) fn remap_reference(reference: i64, index: i64, old_len: i64, new_len: i64) -> i64 {
+    if reference < index {
+        reference
+    } else if reference < index + old_len {
+        index + (reference - index).min(new_len - 1).max(0)
+    } else {
+        reference + new_len - old_len
+    }
+}
+
+/// Moves the `column` references of `table` (bookmark or dictation) along with
+/// their segments. When a merge makes two of them point at the same segment the
+/// later one is dropped, the table allows one per segment. The revision keeps a
+/// snapshot of it, see `snapshot_references`.
+async fn remap_references(
+    conn: &mut SqliteConnection,
+    table: &str,
+    column: &str,
+    audio_id: &str,
+    index: i64,
+    old_len: i64,
+    new_len: i64,
+) -> Result<(), sqlx::Error> {
+    let references: Vec<(i64, i64)> = sqlx::query_as(&format!(
+        "SELECT id, {column} FROM {table} WHERE audioId = ? ORDER BY {column} ASC"
+    ))
+    .bind(audio_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut taken = HashSet::new();
+    let mut moved = Vec::new();
+
+    for (id, reference) in references {
+        let target = remap_reference(reference, index, old_len, new_len);
+
+        if !taken.insert(target) {
+            sqlx::query(&format!("DELETE FROM {table} WHERE id = ?"))
+                .bind(id)
+                .execute(&mut *conn)
+                .await?;
+        } else if target != reference {
+            moved.push((id, target));
+        }
+    }
+
+    // Park the moved rows on negative values first so they can't collide with
+    // rows that haven't moved yet
+    for (id, target) in moved {
+        sqlx::query(&format!("UPDATE {table} SET {column} = ? WHERE id = ?"))
+            .bind(-target - 1)
+            .bind(id)
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    sqlx::query(&format!(
+        "UPDATE {table} SET {column} = -{column} - 1 WHERE audioId = ? AND {column} < 0"
+    ))
+    .bind(audio_id)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Replaces `old_len` segments starting at `index` with `segments`, shifting
+/// the following segments and the bookmarks/dictations/attempts pointing at them.
+async fn splice_transcript(
+    conn: &mut SqliteConnection,
+    audio_id: &str,
+    index: i64,
+    old_len: i64,
+    segments: &[Segment],
+) -> Result<(), sqlx::Error> {
+    let new_len = segments.len() as i64;
+
+    sqlx::query(
+        "DELETE FROM transcript_segment WHERE audioId = ? AND segmentIndex >= ? AND segmentIndex < ?",
+    )
+    .bind(audio_id)
+    .bind(index)
+    .bind(index + old_len)
+    .execute(&mut *conn)
+    .await?;
+
+    if new_len != old_len {
+        sqlx::query(
+            r#"
+            UPDATE transcript_segment SET segmentIndex = -(segmentIndex + ?) - 1
+            WHERE audioId = ? AND segmentIndex >= ?
+            "#,
+        )
+        .bind(new_len - old_len)
+        .bind(audio_id)
+        .bind(index + old_len)
+        .execute(&mut *conn)
+        .await?;
+
+        sqlx::query(
+            "UPDATE transcript_segment SET segmentIndex = -segmentIndex - 1 WHERE audioId = ? AND segmentIndex < 0",
+        )
+        .bind(audio_id)
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    for (offset, segment) in segments.iter().enumerate() {
+        sqlx::query(
+            r#"
+            INSERT INTO transcript_segment (audioId, segmentIndex, startTime, endTime, text, words)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(audio_id)
+        .bind(index + offset as i64)
+        .bind(segment.start)
+        .bind(segment.end)
+        .bind(&segment.text)
+        .bind(segment.words.as_ref().map(Json))
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    remap_references(
+        conn,
+        "bookmark",
+        "bookmarkId",
+        audio_id,
+        index,
+        old_len,
+        new_len,
+    )
+    .await?;
+    remap_references(
+        conn,
+        "dictation",
+        "dictationId",
+        audio_id,
+        index,
+        old_len,
+        new_len,
+    )
+    .await?;
+
+    // Attempts aren't unique per segment, they all follow it like remap_reference does
+    sqlx::query(
+        r#"
+        UPDATE dictation_attempt SET segmentIndex = CASE
+            WHEN segmentIndex < ?2 + ?3 THEN ?2 + MIN(segmentIndex - ?2, ?4 - 1)
+            ELSE segmentIndex + ?4 - ?3
+        END
+        WHERE audioId = ?1 AND segmentIndex >= ?2
+        "#,
+    )
+    .bind(audio_id)
+    .bind(index)
+    .bind(old_len)
+    .bind(new_len)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// JSON snapshot of the bookmark, dictation and attempt rows pointing at the
+/// `len` segments starting at `index`.
+async fn snapshot_references(
+    conn: &mut SqliteConnection,
+    audio_id: &str,
+    index: i64,
+    len: i64,
+) -> Result<String, sqlx::Error> {
+    sqlx::query_scalar(
+        r#"
+        SELECT json_object(
+            'bookmark', (
+                SELECT json_group_array(json_object(
+                    'id', id, 'userId', userId, 'bookmarkId', bookmarkId, 'createdAt', createdAt,
+                    'easeFactor', easeFactor, 'intervalDays', intervalDays,
+                    'repetitions', repetitions, 'dueAt', dueAt, 'lastReviewedAt', lastReviewedAt
+                ))
+                FROM bookmark WHERE audioId = ?1 AND bookmarkId >= ?2 AND bookmarkId < ?2 + ?3
+            ),
+            'dictation', (
+                SELECT json_group_array(json_object(
+                    'id', id, 'userId', userId, 'dictationId', dictationId, 'createdAt', createdAt
+                ))
+                FROM dictation WHERE audioId = ?1 AND dictationId >= ?2 AND dictationId < ?2 + ?3
+            ),
+            'attempt', (
+                SELECT json_group_array(json_object('id', id, 'segmentIndex', segmentIndex))
+                FROM dictation_attempt
+                WHERE audioId = ?1 AND segmentIndex >= ?2 AND segmentIndex < ?2 + ?3
+            )
+        )
+        "#,
+    )
+    .bind(audio_id)
+    .bind(index)
+    .bind(len)
+    .fetch_one(&mut *conn)
+    .await
+}
+
+/// Puts the rows of a `snapshot_references` snapshot back where they were.
+/// Rows that were moved or dropped by the edit are replaced by their snapshot,
+/// a reference added to the same segment since then is kept instead.
+async fn restore_references(
+    conn: &mut SqliteConnection,
+    audio_id: &str,
+    snapshot: &str,
+) -> Result<(), sqlx::Error> {
+    for table in ["bookmark", "dictation"] {
+        sqlx::query(&format!(
+            r#"
+            DELETE FROM {table} WHERE id IN (
+                SELECT json_extract(value, '$.id') FROM json_each(?, '$.{table}')
+            )
+            "#
+        ))
+        .bind(snapshot)
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    sqlx::query(
+        r#"
+        INSERT OR IGNORE INTO bookmark (
+            id, userId, audioId, bookmarkId, createdAt,
+            easeFactor, intervalDays, repetitions, dueAt, lastReviewedAt
+        )
+        SELECT
+            json_extract(value, '$.id'), json_extract(value, '$.userId'), ?1,
+            json_extract(value, '$.bookmarkId'), json_extract(value, '$.createdAt'),
+            json_extract(value, '$.easeFactor'), json_extract(value, '$.intervalDays'),
+            json_extract(value, '$.repetitions'), json_extract(value, '$.dueAt'),
+            json_extract(value, '$.lastReviewedAt')
+        FROM json_each(?2, '$.bookmark')
+        "#,
+    )
+    .bind(audio_id)
+    .bind(snapshot)
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query(
+        r#"
+        INSERT OR IGNORE INTO dictation (id, userId, audioId, dictationId, createdAt)
+        SELECT
+            json_extract(value, '$.id'), json_extract(value, '$.userId'), ?1,
+            json_extract(value, '$.dictationId'), json_extract(value, '$.createdAt')
+        FROM json_each(?2, '$.dictation')
+        "#,
+    )
+    .bind(audio_id)
+    .bind(snapshot)
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query(
+        r#"
+        UPDATE dictation_attempt SET segmentIndex = (
+            SELECT json_extract(value, '$.segmentIndex') FROM json_each(?1, '$.attempt')
+            WHERE json_extract(value, '$.id') = dictation_attempt.id
+        )
+        WHERE id IN (SELECT json_extract(value, '$.id') FROM json_each(?1, '$.attempt'))
+        "#,
+    )
+    .bind(snapshot)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Applies an edit replacing `old_segments` at `index` with `new_segments` and
+/// records it as a revision by `user_id`.
+pub async fn edit_transcript(
+    db: &Db,
+    user_id: &str,
+    audio_id: &str,
+    action: &str,
+    index: i64,
+    old_segments: &[Segment],
+    new_segments: &[Segment],
+) -> Result<(), sqlx::Error> {
+    let mut tx = db.begin().await?;
+
+    let old_references =
+        snapshot_references(&mut tx, audio_id, index, old_segments.len() as i64).await?;
+
+    splice_transcript(
+        &mut tx,
+        audio_id,
+        index,
+        old_segments.len() as i64,
+        new_segments,
+    )
+    .await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO transcript_revision (audioId, userId, action, segmentIndex, oldSegments, newSegments, oldReferences)
+        VALUES (?, ?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(audio_id)
+    .bind(user_id)
+    .bind(action)
+    .bind(index)
+    .bind(Json(old_segments))
+    .bind(Json(new_segments))
+    .bind(old_references)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await
+}
+
+/// Reverts the latest edit of the transcript that wasn't undone yet. Returns
+/// `None` if there is nothing to undo.
+pub async fn undo_transcript_edit(
+    db: &Db,
+    audio_id: &str,
+) -> Result<Option<TranscriptRevision>, sqlx::Error> {
+    let mut tx = db.begin().await?;
+
+    let revision = sqlx::query_as::<_, TranscriptRevision>(
+        "SELECT * FROM transcript_revision WHERE audioId = ? AND undone = 0 ORDER BY id DESC LIMIT 1",
+    )
+    .bind(audio_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let Some(revision) = revision else {
+        return Ok(None);
+    };
+
+    splice_transcript(
+        &mut tx,
+        audio_id,
+        revision.segment_index,
+        revision.new_segments.len() as i64,
+        &revision.old_segments,
+    )
+    .await?;
+
+    let old_references: String =
+        sqlx::query_scalar("SELECT oldReferences FROM transcript_revision WHERE id = ?")
+            .bind(revision.id)
+            .fetch_one(&mut *tx)
+            .await?;
+
+    restore_references(&mut tx, audio_id, &old_references).await?;
+
+    sqlx::query("UPDATE transcript_revision SET undone = 1 WHERE id = ?")
+        .bind(revision.id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(Some(revision))
+}
+
+pub async fn get_transcript_revisions(
+    db: &Db,
+    user_id: &str,
+    audio_id: &str,
+) -> Result<Vec<TranscriptRevision>, sqlx::Error> {
+    sqlx::query_as::<_, TranscriptRevision>(
+        r#"
+        SELECT r.* FROM transcript_revision r
+        JOIN audio a ON a.id = r.audioId
+        WHERE a.userId = ? AND r.audioId = ?
+        ORDER BY r.id DESC
+        "#,
+    )
+    .bind(user_id)
+    .bind(audio_id)
+    .fetch_all(db)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_db;
+
+    fn segment(text: &str, start: f64) -> Segment {
+        Segment {
+            start,
+            end: start + 1.0,
+            text: text.to_string(),
+            words: None,
+        }
+    }
+
+    async fn references(db: &Db) -> Vec<(String, i64, i64, f64)> {
+        sqlx::query_as(
+            r#"
+            SELECT 'bookmark', id, bookmarkId, easeFactor FROM bookmark WHERE audioId = 'a1'
+            UNION ALL
+            SELECT 'dictation', id, dictationId, 0.0 FROM dictation WHERE audioId = 'a1'
+            UNION ALL
+            SELECT 'attempt', id, segmentIndex, accuracy FROM dictation_attempt WHERE audioId = 'a1'
+            ORDER BY 1, 2
+            "#,
+        )
+        .fetch_all(db)
+        .await
+        .unwrap()
+    }
+
+    #[test]
+    fn remaps_references_after_split() {
+        // Segment 2 split in two
+        assert_eq!(remap_reference(1, 2, 1, 2), 1);
+        assert_eq!(remap_reference(2, 2, 1, 2), 2);
+        assert_eq!(remap_reference(3, 2, 1, 2), 4);
+    }
+
+    #[test]
+    fn remaps_references_after_merge() {
+        // Segments 2 and 3 merged
+        assert_eq!(remap_reference(1, 2, 2, 1), 1);
+        assert_eq!(remap_reference(2, 2, 2, 1), 2);
+        assert_eq!(remap_reference(3, 2, 2, 1), 2);
+        assert_eq!(remap_reference(4, 2, 2, 1), 3);
+    }
+
+    #[test]
+    fn keeps_references_on_in_place_edits() {
+        for reference in 0..5 {
+            assert_eq!(remap_reference(reference, 2, 1, 1), reference);
+        }
+    }
+
+    #[tokio::test]
+    async fn undo_restores_references_dropped_by_a_merge() {
+        let db = test_db().await;
+        let segments = [segment("a", 0.0), segment("b", 1.0), segment("c", 2.0)];
+
+        sqlx::query(
+            r#"
+            INSERT INTO user (id, name) VALUES ('u1', 'one');
+            INSERT INTO audio (id, userId, title, url, startTime, endTime, provider)
+            VALUES ('a1', 'u1', 'first', 'https://example.com/1', 0, 60, 'http');
+            INSERT INTO bookmark (userId, audioId, bookmarkId, easeFactor, repetitions)
+            VALUES ('u1', 'a1', 0, 2.6, 3), ('u1', 'a1', 1, 1.8, 5), ('u1', 'a1', 2, 2.5, 0);
+            INSERT INTO dictation (userId, audioId, dictationId) VALUES ('u1', 'a1', 0), ('u1', 'a1', 1);
+            INSERT INTO dictation_attempt (userId, audioId, segmentIndex, expectedText, typedText, marks, accuracy)
+            VALUES ('u1', 'a1', 1, 'b', 'b', '[]', 1.0);
+            "#,
+        )
+        .execute(&db)
+        .await
+        .unwrap();
+        replace_transcript(&db, "a1", &segments).await.unwrap();
+
+        let before = references(&db).await;
+
+        edit_transcript(
+            &db,
+            "u1",
+            "a1",
+            "merge",
+            0,
+            &segments[..2],
+            &[segment("a b", 0.0)],
+        )
+        .await
+        .unwrap();
+
+        let merged = references(&db).await;
+        assert_eq!(merged.iter().filter(|r| r.0 == "bookmark").count(), 2);
+        assert_eq!(merged.iter().filter(|r| r.0 == "dictation").count(), 1);
+
+        undo_transcript_edit(&db, "a1").await.unwrap().unwrap();
+
+        assert_eq!(references(&db).await, before);
+        let repetitions: i64 =
+            sqlx::query_scalar("SELECT repetitions FROM bookmark WHERE bookmarkId = 1")
+                .fetch_one(&db)
+                .await
+                .unwrap();
+        assert_eq!(repetitions, 5);
+    }
+}
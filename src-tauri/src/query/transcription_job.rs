@@ -0,0 +1,174 @@
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+
+use crate::db::Db;
+
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptionJob {
+    #[sqlx(rename = "audioId")]
+    pub audio_id: String,
+
+    #[sqlx(rename = "userId")]
+    pub user_id: String,
+
+    pub status: String,
+    pub model: String,
+    pub language: String,
+    pub progress: Option<f64>,
+    pub message: Option<String>,
+    pub error: Option<String>,
+
+    #[sqlx(rename = "createdAt")]
+    created_at: String,
+
+    #[sqlx(rename = "updatedAt")]
+    updated_at: String,
+}
+
+/// Queues a new transcription of the audio, replacing the previous job unless
+/// that one is still queued or running. Returns `None` in that case.
+pub async fn create_transcription_job(
+    db: &Db,
+    user_id: &str,
+    audio_id: &str,
+    model: &str,
+    language: &str,
+) -> Result<Option<TranscriptionJob>, sqlx::Error> {
+    sqlx::query_as::<_, TranscriptionJob>(
+        r#"
+        INSERT INTO transcription_job (audioId, userId, model, language)
+        VALUES (?, ?, ?, ?)
+        ON CONFLICT (audioId) DO UPDATE SET
+            userId = excluded.userId,
+            status = 'queued',
+            model = excluded.model,
+            language = excluded.language,
+            progress = NULL,
+            message = NULL,
+            error = NULL,
+            createdAt = CURRENT_TIMESTAMP,
+            updatedAt = CURRENT_TIMESTAMP
+        WHERE transcription_job.status NOT IN ('queued', 'running')
+        RETURNING *
+        "#,
+    )
+    .bind(audio_id)
+    .bind(user_id)
+    .bind(model)
+    .bind(language)
+    .fetch_optional(db)
+    .await
+}
+
+pub async fn get_transcription_job(
+    db: &Db,
+    user_id: &str,
+    audio_id: &str,
+) -> Result<Option<TranscriptionJob>, sqlx::Error> {
+    sqlx::query_as::<_, TranscriptionJob>(
+        "SELECT * FROM transcription_job WHERE userId = ? AND audioId = ?",
+    )
+    .bind(user_id)
+    .bind(audio_id)
+    .fetch_optional(db)
+    .await
+}
+
+/// Moves a queued job to running. Returns `false` if it was cancelled while waiting.
+pub async fn start_transcription_job(db: &Db, audio_id: &str) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE transcription_job SET status = 'running', updatedAt = CURRENT_TIMESTAMP WHERE audioId = ? AND status = 'queued'",
+    )
+    .bind(audio_id)
+    .execute(db)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn update_transcription_progress(
+    db: &Db,
+    audio_id: &str,
+    progress: Option<f64>,
+    message: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE transcription_job
+        SET progress = COALESCE(?, progress), message = ?, updatedAt = CURRENT_TIMESTAMP
+        WHERE audioId = ? AND status = 'running'
+        "#,
+    )
+    .bind(progress)
+    .bind(message)
+    .bind(audio_id)
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+/// Marks a running job as done. Returns `false` if it was cancelled in the meantime.
+pub async fn finish_transcription_job(db: &Db, audio_id: &str) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE transcription_job SET status = 'done', progress = 1.0, error = NULL, updatedAt = CURRENT_TIMESTAMP WHERE audioId = ? AND status = 'running'",
+    )
+    .bind(audio_id)
+    .execute(db)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn fail_transcription_job(
+    db: &Db,
+    audio_id: &str,
+    error: String,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE transcription_job SET status = 'failed', error = ?, updatedAt = CURRENT_TIMESTAMP WHERE audioId = ? AND status IN ('queued', 'running')",
+    )
+    .bind(error)
+    .bind(audio_id)
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+/// Cancels a job that is queued or running. Returns `false` if there was none.
+pub async fn cancel_transcription_job(
+    db: &Db,
+    user_id: &str,
+    audio_id: &str,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        r#"
+        UPDATE transcription_job
+        SET status = 'cancelled', updatedAt = CURRENT_TIMESTAMP
+        WHERE userId = ? AND audioId = ? AND status IN ('queued', 'running')
+        "#,
+    )
+    .bind(user_id)
+    .bind(audio_id)
+    .execute(db)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Jobs don't survive a restart of the app, fail the ones that were in flight.
+pub async fn fail_interrupted_transcription_jobs(db: &Db) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        r#"
+        UPDATE transcription_job
+        SET status = 'failed', error = 'Interrupted by an app restart', updatedAt = CURRENT_TIMESTAMP
+        WHERE status IN ('queued', 'running')
+        "#,
+    )
+    .execute(db)
+    .await?;
+
+    Ok(result.rows_affected())
+}
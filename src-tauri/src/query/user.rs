@@ -1,13 +1,10 @@
 use chrono::{Duration, Utc};
 use serde::{Deserialize, Serialize};
-use sqlx::prelude::FromRow;
-use tauri::AppHandle;
+use sqlx::{prelude::FromRow, SqliteExecutor};
 use uuid::Uuid;
 
 use crate::db::Db;
 
-use super::store::delete_store_token;
-
 pub type Timestamp = i64;
 
 #[derive(Debug, Serialize, Deserialize, FromRow)]
@@ -105,8 +102,46 @@ pub struct Verification {
     updated_at: String,
 }
 
+/// A session as listed to its user, without the token.
 #[derive(Debug, Serialize, Deserialize, FromRow, specta::Type)]
 #[serde(rename_all = "camelCase")]
+pub struct SessionInfo {
+    pub id: String,
+    #[sqlx(rename = "createdAt")]
+    pub created_at: String,
+    #[sqlx(rename = "lastActiveAt")]
+    pub last_active_at: String,
+    #[sqlx(rename = "expiresAt")]
+    pub expires_at: Timestamp,
+    #[sqlx(rename = "absoluteExpiresAt")]
+    pub absolute_expires_at: Timestamp,
+    /// The session of this app
+    pub current: bool,
+}
+
+/// How long sessions last, from `app_settings`.
+#[derive(Debug, Clone, Copy, PartialEq, FromRow)]
+pub struct SessionLifetimes {
+    /// Expiry after the last use
+    #[sqlx(rename = "sessionIdleSeconds")]
+    pub idle_seconds: i64,
+    /// Expiry after sign in, however often the session is used
+    #[sqlx(rename = "sessionAbsoluteSeconds")]
+    pub absolute_seconds: i64,
+}
+
+impl Default for SessionLifetimes {
+    fn default() -> Self {
+        Self {
+            idle_seconds: 86400,
+            absolute_seconds: 30 * 86400,
+        }
+    }
+}
+
+/// A session with its user. It holds the session token, so it never goes to
+/// the frontend, `CurrentUser` does.
+#[derive(Debug, Clone, FromRow)]
 pub struct SessionWithUser {
     pub user_id: String,
     pub access_token: String,
@@ -115,6 +150,37 @@ pub struct SessionWithUser {
     picture: Option<String>,
 }
 
+/// The signed in user as the frontend sees it.
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CurrentUser {
+    pub user_id: String,
+    pub name: String,
+    pub email: String,
+    pub picture: Option<String>,
+}
+
+impl From<SessionWithUser> for CurrentUser {
+    fn from(session: SessionWithUser) -> Self {
+        Self {
+            user_id: session.user_id,
+            name: session.name,
+            email: session.email,
+            picture: session.picture,
+        }
+    }
+}
+
+pub async fn get_session_lifetimes(db: &Db) -> Result<SessionLifetimes, sqlx::Error> {
+    let lifetimes = sqlx::query_as::<_, SessionLifetimes>(
+        "SELECT sessionIdleSeconds, sessionAbsoluteSeconds FROM app_settings LIMIT 1",
+    )
+    .fetch_optional(db)
+    .await?;
+
+    Ok(lifetimes.unwrap_or_default())
+}
+
 pub async fn get_user_by_id(db: &Db, user_id: &str) -> Result<Option<User>, sqlx::Error> {
     sqlx::query_as::<_, User>("SELECT * FROM user WHERE id = ?")
         .bind(user_id)
@@ -124,7 +190,6 @@ pub async fn get_user_by_id(db: &Db, user_id: &str) -> Result<Option<User>, sqlx
 
 pub async fn get_user_by_session_token(
     db: &Db,
-    app_handle: &AppHandle,
     session_token: String,
 ) -> Result<Option<SessionWithUser>, sqlx::Error> {
     let result = sqlx::query_as::<_, SessionWithUser>(
@@ -154,15 +219,18 @@ pub async fn get_user_by_session_token(
         .bind(session_token)
         .execute(db)
         .await?;
-        let _ = delete_store_token(&app_handle).unwrap();
     } else {
+        let lifetimes = get_session_lifetimes(db).await?;
+
+        // Slides with every use, up to the absolute expiry
         sqlx::query(
             r#"
             UPDATE session
-            SET expiresAt = unixepoch() + 86400
+            SET expiresAt = MIN(unixepoch() + ?, absoluteExpiresAt)
             WHERE token = ?
             "#,
         )
+        .bind(lifetimes.idle_seconds)
         .bind(session_token)
         .execute(db)
         .await?;
@@ -172,7 +240,7 @@ pub async fn get_user_by_session_token(
 }
 
 pub async fn create_user(
-    db: &Db,
+    db: impl SqliteExecutor<'_>,
     name: String,
     email: String,
     email_verified: bool,
@@ -193,7 +261,7 @@ pub async fn create_user(
 }
 
 pub async fn create_account(
-    db: &Db,
+    db: impl SqliteExecutor<'_>,
     user_id: String,
     account_id: String,
     provider_id: String,
@@ -204,8 +272,8 @@ pub async fn create_account(
 ) -> Result<Account, sqlx::Error> {
     let id = Uuid::new_v4().to_string();
 
-    sqlx::query(
-        "INSERT INTO account (id, userId, providerId, accountId, accessToken, accessTokenExpiresAt, refreshToken, refreshTokenExpiresAt, createdAt, updatedAt) VALUES (?, ?, ?, ?, ?, ?, ?, ?, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)")
+    sqlx::query_as::<_, Account>(
+        "INSERT INTO account (id, userId, providerId, accountId, accessToken, accessTokenExpiresAt, refreshToken, refreshTokenExpiresAt, createdAt, updatedAt) VALUES (?, ?, ?, ?, ?, ?, ?, ?, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP) RETURNING *")
     .bind(&id)
     .bind(&user_id)
     .bind(&provider_id)
@@ -214,24 +282,19 @@ pub async fn create_account(
     .bind(&access_token_expires_at)
     .bind(&refresh_token)
     .bind(&refresh_token_expires_at)
-    .execute(db)
+    .fetch_one(db)
     .await
-    .expect("account error");
-
-    let account = sqlx::query_as::<_, Account>("SELECT * FROM account WHERE id = ?")
-        .bind(id)
-        .fetch_one(db)
-        .await?;
-
-    Ok(account)
 }
 
 pub async fn create_session(db: &Db, user_id: String) -> Result<String, sqlx::Error> {
     let id = Uuid::new_v4().to_string();
     let token = Uuid::new_v4().to_string();
+    let lifetimes = get_session_lifetimes(db).await?;
     let now = Utc::now();
-    let expires_at = now + Duration::seconds(86400 as i64);
-    let expires_at_timestamp = expires_at.timestamp();
+    let absolute_expires_at = (now + Duration::seconds(lifetimes.absolute_seconds)).timestamp();
+    let expires_at_timestamp = (now + Duration::seconds(lifetimes.idle_seconds))
+        .timestamp()
+        .min(absolute_expires_at);
 
     sqlx::query(
         r#"
@@ -240,10 +303,11 @@ pub async fn create_session(db: &Db, user_id: String) -> Result<String, sqlx::Er
             userId,
             token,
             expiresAt,
+            absoluteExpiresAt,
             createdAt,
             updatedAt
         ) VALUES (
-            ?, ?, ?, ?, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP
+            ?, ?, ?, ?, ?, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP
         )
         "#,
     )
@@ -251,6 +315,7 @@ pub async fn create_session(db: &Db, user_id: String) -> Result<String, sqlx::Er
     .bind(&user_id)
     .bind(&token)
     .bind(&expires_at_timestamp)
+    .bind(absolute_expires_at)
     .execute(db)
     .await?;
 
@@ -271,6 +336,67 @@ pub async fn delete_session(db: &Db, session_token: String) -> Result<(), sqlx::
     Ok(())
 }
 
+/// Deletes every session of the user, returns how many there were.
+pub async fn delete_user_sessions(db: &Db, user_id: &str) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM session WHERE userId = ?")
+        .bind(user_id)
+        .execute(db)
+        .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// The live sessions of the user, most recently used first.
+pub async fn get_user_sessions(
+    db: &Db,
+    user_id: &str,
+    current_token: &str,
+) -> Result<Vec<SessionInfo>, sqlx::Error> {
+    sqlx::query_as::<_, SessionInfo>(
+        r#"
+        SELECT
+            id,
+            createdAt,
+            updatedAt AS lastActiveAt,
+            expiresAt,
+            absoluteExpiresAt,
+            token = ? AS current
+        FROM session
+        WHERE userId = ? AND expiresAt > unixepoch()
+        ORDER BY updatedAt DESC, createdAt DESC
+        "#,
+    )
+    .bind(current_token)
+    .bind(user_id)
+    .fetch_all(db)
+    .await
+}
+
+/// Deletes a session of the user and returns its token, `None` if the user has no such session.
+pub async fn delete_user_session(
+    db: &Db,
+    user_id: &str,
+    session_id: &str,
+) -> Result<Option<String>, sqlx::Error> {
+    sqlx::query_scalar("DELETE FROM session WHERE id = ? AND userId = ? RETURNING token")
+        .bind(session_id)
+        .bind(user_id)
+        .fetch_optional(db)
+        .await
+}
+
+/// Deletes the expired sessions and verifications, returns how many rows went.
+pub async fn purge_expired_sessions(db: &Db) -> Result<u64, sqlx::Error> {
+    let sessions = sqlx::query("DELETE FROM session WHERE expiresAt <= unixepoch()")
+        .execute(db)
+        .await?;
+    let verifications = sqlx::query("DELETE FROM verification WHERE expiresAt <= unixepoch()")
+        .execute(db)
+        .await?;
+
+    Ok(sessions.rows_affected() + verifications.rows_affected())
+}
+
 pub async fn update_user_name(
     db: &Db,
     user_id: &str,
@@ -290,3 +416,174 @@ pub async fn update_user_name(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_db;
+
+    async fn session_expiry(db: &Db, token: &str) -> (i64, i64) {
+        sqlx::query_as(
+            r#"
+            SELECT expiresAt - unixepoch(), absoluteExpiresAt - unixepoch()
+            FROM session
+            WHERE token = ?
+            "#,
+        )
+        .bind(token)
+        .fetch_one(db)
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn sessions_slide_up_to_the_absolute_expiry() {
+        let db = test_db().await;
+        sqlx::query(
+            "UPDATE app_settings SET sessionIdleSeconds = 600, sessionAbsoluteSeconds = 3600",
+        )
+        .execute(&db)
+        .await
+        .unwrap();
+        let user_id = create_user(&db, "Ann".into(), "ann@example.com".into(), true, None)
+            .await
+            .unwrap();
+
+        let token = create_session(&db, user_id).await.unwrap();
+        let (idle, absolute) = session_expiry(&db, &token).await;
+        assert!((599..=600).contains(&idle), "{}", idle);
+        assert!((3599..=3600).contains(&absolute), "{}", absolute);
+
+        // Close to the absolute expiry a lookup can't extend past it
+        sqlx::query("UPDATE session SET absoluteExpiresAt = unixepoch() + 60")
+            .execute(&db)
+            .await
+            .unwrap();
+        assert!(get_user_by_session_token(&db, token.clone())
+            .await
+            .unwrap()
+            .is_some());
+        let (idle, absolute) = session_expiry(&db, &token).await;
+        assert_eq!(idle, absolute);
+    }
+
+    #[tokio::test]
+    async fn purges_expired_rows() {
+        let db = test_db().await;
+        let user_id = create_user(&db, "Ann".into(), "ann@example.com".into(), true, None)
+            .await
+            .unwrap();
+        let live = create_session(&db, user_id.clone()).await.unwrap();
+        let expired = create_session(&db, user_id).await.unwrap();
+        sqlx::query("UPDATE session SET expiresAt = unixepoch() - 1 WHERE token = ?")
+            .bind(&expired)
+            .execute(&db)
+            .await
+            .unwrap();
+        sqlx::query(
+            r#"
+            INSERT INTO verification (id, identifier, value, expiresAt) VALUES
+                ('v1', 'ann@example.com', 'old', unixepoch() - 1),
+                ('v2', 'ann@example.com', 'new', unixepoch() + 600)
+            "#,
+        )
+        .execute(&db)
+        .await
+        .unwrap();
+
+        assert_eq!(purge_expired_sessions(&db).await.unwrap(), 2);
+
+        let tokens: Vec<String> = sqlx::query_scalar("SELECT token FROM session")
+            .fetch_all(&db)
+            .await
+            .unwrap();
+        assert_eq!(tokens, vec![live]);
+        let verifications: Vec<String> = sqlx::query_scalar("SELECT id FROM verification")
+            .fetch_all(&db)
+            .await
+            .unwrap();
+        assert_eq!(verifications, vec!["v2".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn users_only_see_and_revoke_their_sessions() {
+        let db = test_db().await;
+        let ann = create_user(&db, "Ann".into(), "ann@example.com".into(), true, None)
+            .await
+            .unwrap();
+        let bob = create_user(&db, "Bob".into(), "bob@example.com".into(), true, None)
+            .await
+            .unwrap();
+        let current = create_session(&db, ann.clone()).await.unwrap();
+        create_session(&db, ann.clone()).await.unwrap();
+        let bobs = create_session(&db, bob.clone()).await.unwrap();
+
+        let sessions = get_user_sessions(&db, &ann, &current).await.unwrap();
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions.iter().filter(|session| session.current).count(), 1);
+
+        let bob_session = &get_user_sessions(&db, &bob, &bobs).await.unwrap()[0];
+        assert_eq!(
+            delete_user_session(&db, &ann, &bob_session.id)
+                .await
+                .unwrap(),
+            None
+        );
+
+        let other = sessions.iter().find(|session| !session.current).unwrap();
+        assert!(delete_user_session(&db, &ann, &other.id)
+            .await
+            .unwrap()
+            .is_some());
+        assert_eq!(
+            get_user_sessions(&db, &ann, &current).await.unwrap().len(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn logged_out_token_no_longer_resolves() {
+        let db = test_db().await;
+        let user_id = create_user(&db, "Ann".into(), "ann@example.com".into(), true, None)
+            .await
+            .unwrap();
+        let token = create_session(&db, user_id.clone()).await.unwrap();
+
+        let user = get_user_by_session_token(&db, token.clone()).await.unwrap();
+        assert_eq!(user.map(|user| user.user_id), Some(user_id));
+
+        delete_session(&db, token.clone()).await.unwrap();
+
+        assert!(get_user_by_session_token(&db, token)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn logout_everywhere_keeps_other_users_signed_in() {
+        let db = test_db().await;
+        let ann = create_user(&db, "Ann".into(), "ann@example.com".into(), true, None)
+            .await
+            .unwrap();
+        let bob = create_user(&db, "Bob".into(), "bob@example.com".into(), true, None)
+            .await
+            .unwrap();
+        let laptop = create_session(&db, ann.clone()).await.unwrap();
+        let desktop = create_session(&db, ann.clone()).await.unwrap();
+        let bobs = create_session(&db, bob).await.unwrap();
+
+        assert_eq!(delete_user_sessions(&db, &ann).await.unwrap(), 2);
+
+        for token in [laptop, desktop] {
+            assert!(get_user_by_session_token(&db, token)
+                .await
+                .unwrap()
+                .is_none());
+        }
+        assert!(get_user_by_session_token(&db, bobs)
+            .await
+            .unwrap()
+            .is_some());
+    }
+}
@@ -0,0 +1,261 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::Notify;
+use uuid::Uuid;
+
+use crate::{
+    auth::require_user,
+    config::get_data_path,
+    db::Db,
+    error::AppError,
+    provider::provider_for_url,
+    query::{
+        audio::create_audio,
+        commands::{remove_dir_all_safe, store_audio_content_hash},
+        download_queue::{
+            claim_next_download, enqueue_download as insert_download, fail_download,
+            finish_download, get_download_queue as get_queue, get_download_queue_item,
+            requeue_running_downloads, retry_download_later, DownloadQueueItem,
+            EnqueueDownloadRequest,
+        },
+        setting::get_app_settings,
+    },
+    yt::{check_section, DownloadOutcome},
+    DbState,
+};
+
+const MAX_ATTEMPTS: i64 = 3;
+const RETRY_BASE_DELAY_SECS: i64 = 30;
+const DEFAULT_MAX_CONCURRENT_DOWNLOADS: usize = 2;
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+// yt-dlp errors that won't go away by trying again
+const PERMANENT_ERRORS: [&str; 6] = [
+    "Unsupported URL",
+    "is not a valid URL",
+    "Video unavailable",
+    "Private video",
+    "Sign in to confirm your age",
+    "members-only",
+];
+
+#[derive(Default)]
+pub struct DownloadQueueState {
+    notify: Notify,
+}
+
+impl DownloadQueueState {
+    /// Wakes the worker up so it doesn't wait for the next poll.
+    pub fn wake(&self) {
+        self.notify.notify_one();
+    }
+}
+
+fn is_transient_error(error: &str) -> bool {
+    !PERMANENT_ERRORS.iter().any(|marker| error.contains(marker))
+}
+
+fn retry_delay_secs(attempts: i64) -> i64 {
+    RETRY_BASE_DELAY_SECS * 2_i64.pow((attempts - 1).clamp(0, 10) as u32)
+}
+
+async fn emit_queue_item(app_handle: &AppHandle, db: &Db, id: &str) {
+    if let Ok(item) = get_download_queue_item(db, id).await {
+        let _ = app_handle.emit("download_queue", item);
+    }
+}
+
+async fn process_download(app_handle: &AppHandle, db: &Db, item: DownloadQueueItem) {
+    emit_queue_item(app_handle, db, &item.id).await;
+
+    // Every attempt starts from an empty directory, an interrupted one may have left partial files
+    let data_path = get_data_path(app_handle).unwrap_or("/data/".to_string());
+    let audio_dir = format!("{}/{}", data_path, item.id);
+    let _ = remove_dir_all_safe(&audio_dir).await;
+
+    let provider = match provider_for_url(&item.url) {
+        Ok(provider) => provider,
+        Err(error) => {
+            if let Err(e) = fail_download(db, &item.id, error).await {
+                println!("❌ Failed to update download queue item {}: {}", item.id, e);
+            }
+            emit_queue_item(app_handle, db, &item.id).await;
+            return;
+        }
+    };
+
+    let result = provider
+        .download_section(
+            app_handle,
+            &item.id,
+            &item.url,
+            item.start_time.into(),
+            item.end_time.into(),
+        )
+        .await;
+
+    let update = match result {
+        Ok(DownloadOutcome::Finished) => match finish_download(db, &item.id).await {
+            Ok(true) => {
+                let thumbnail = match item.thumbnail.clone() {
+                    Some(thumbnail) => thumbnail,
+                    None => provider
+                        .thumbnail(app_handle, &item.url)
+                        .await
+                        .ok()
+                        .flatten()
+                        .unwrap_or_default(),
+                };
+
+                let created = create_audio(
+                    db,
+                    item.user_id.clone(),
+                    item.id.clone(),
+                    item.title.clone(),
+                    item.description.clone(),
+                    item.url.clone(),
+                    thumbnail,
+                    item.start_time,
+                    item.end_time,
+                    item.provider,
+                    item.tag.clone(),
+                )
+                .await;
+
+                match created {
+                    Ok(()) => {
+                        store_audio_content_hash(app_handle, db, &item.id).await;
+                        Ok(())
+                    }
+                    Err(e) => fail_download(db, &item.id, e.to_string()).await,
+                }
+            }
+            // Cancelled while the download was finishing
+            Ok(false) => remove_dir_all_safe(&audio_dir)
+                .await
+                .map_err(sqlx::Error::Io),
+            Err(e) => Err(e),
+        },
+        // `cancel_download` already updated the row and removed the directory
+        Ok(DownloadOutcome::Cancelled) => Ok(()),
+        Err(error) if item.attempts < MAX_ATTEMPTS && is_transient_error(&error) => {
+            retry_download_later(db, &item.id, error, retry_delay_secs(item.attempts)).await
+        }
+        Err(error) => fail_download(db, &item.id, error).await,
+    };
+
+    if let Err(e) = update {
+        println!("❌ Failed to update download queue item {}: {}", item.id, e);
+    }
+
+    emit_queue_item(app_handle, db, &item.id).await;
+}
+
+/// Spawns the background worker that runs queued downloads, at most
+/// `maxConcurrentDownloads` (from `app_settings`) at a time.
+pub fn start_download_worker(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let db = app_handle.state::<DbState>().db.clone();
+
+        match requeue_running_downloads(&db).await {
+            Ok(0) => {}
+            Ok(count) => println!("🔁 Resuming {} interrupted download(s)", count),
+            Err(e) => println!("❌ Failed to resume interrupted downloads: {}", e),
+        }
+
+        let running = Arc::new(AtomicUsize::new(0));
+
+        loop {
+            let max_concurrent = get_app_settings(&db)
+                .await
+                .map(|settings| settings.max_concurrent_downloads.max(1) as usize)
+                .unwrap_or(DEFAULT_MAX_CONCURRENT_DOWNLOADS);
+
+            while running.load(Ordering::SeqCst) < max_concurrent {
+                let item = match claim_next_download(&db).await {
+                    Ok(Some(item)) => item,
+                    Ok(None) => break,
+                    Err(e) => {
+                        println!("❌ Failed to read download queue: {}", e);
+                        break;
+                    }
+                };
+
+                running.fetch_add(1, Ordering::SeqCst);
+
+                let app_handle = app_handle.clone();
+                let db = db.clone();
+                let running = running.clone();
+                tauri::async_runtime::spawn(async move {
+                    process_download(&app_handle, &db, item).await;
+
+                    running.fetch_sub(1, Ordering::SeqCst);
+                    app_handle.state::<DownloadQueueState>().wake();
+                });
+            }
+
+            let queue_state = app_handle.state::<DownloadQueueState>();
+            tokio::select! {
+                _ = queue_state.notify.notified() => {}
+                _ = tokio::time::sleep(POLL_INTERVAL) => {}
+            }
+        }
+    });
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn enqueue_download(
+    app_handle: AppHandle,
+    state: tauri::State<'_, DbState>,
+    queue_state: tauri::State<'_, DownloadQueueState>,
+    request: EnqueueDownloadRequest,
+) -> Result<DownloadQueueItem, AppError> {
+    let db = &state.db;
+
+    let user = require_user(&app_handle, db).await?;
+
+    let provider = provider_for_url(&request.url).map_err(AppError::Validation)?;
+
+    check_section(
+        &app_handle,
+        provider.as_ref(),
+        &request.url,
+        request.start_time.into(),
+        request.end_time.into(),
+    )
+    .await?;
+
+    let item = insert_download(
+        db,
+        user.user_id,
+        Uuid::new_v4().to_string(),
+        provider.kind(),
+        request,
+    )
+    .await?;
+
+    queue_state.wake();
+
+    Ok(item)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_download_queue(
+    app_handle: AppHandle,
+    state: tauri::State<'_, DbState>,
+) -> Result<Vec<DownloadQueueItem>, AppError> {
+    let db = &state.db;
+
+    let user = require_user(&app_handle, db).await?;
+
+    Ok(get_queue(db, user.user_id).await?)
+}
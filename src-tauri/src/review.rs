@@ -0,0 +1,169 @@
+use tauri::AppHandle;
+
+use crate::{
+    auth::require_user,
+    error::AppError,
+    query::review::{
+        get_due_reviews as get_due_cards, get_review_card, update_review_schedule, ReviewCard,
+    },
+    DbState,
+};
+
+const MIN_EASE_FACTOR: f64 = 1.3;
+const DEFAULT_REVIEW_LIMIT: i64 = 20;
+
+/// The SM-2 state of a review card.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReviewSchedule {
+    pub ease_factor: f64,
+    pub interval_days: i64,
+    /// Successful reviews in a row
+    pub repetitions: i64,
+}
+
+impl From<&ReviewCard> for ReviewSchedule {
+    fn from(card: &ReviewCard) -> Self {
+        ReviewSchedule {
+            ease_factor: card.ease_factor,
+            interval_days: card.interval_days,
+            repetitions: card.repetitions,
+        }
+    }
+}
+
+/// Next SM-2 schedule after a review graded 0 (blackout) to 5 (perfect).
+/// Grades below 3 start the card over, the ease factor moves with every grade.
+pub(crate) fn next_schedule(schedule: ReviewSchedule, grade: u8) -> ReviewSchedule {
+    let grade = grade.min(5);
+    let miss = f64::from(5 - grade);
+    let ease_factor =
+        (schedule.ease_factor + 0.1 - miss * (0.08 + miss * 0.02)).max(MIN_EASE_FACTOR);
+
+    if grade < 3 {
+        return ReviewSchedule {
+            ease_factor,
+            interval_days: 1,
+            repetitions: 0,
+        };
+    }
+
+    let interval_days = match schedule.repetitions {
+        0 => 1,
+        1 => 6,
+        _ => (schedule.interval_days as f64 * schedule.ease_factor).round() as i64,
+    };
+
+    ReviewSchedule {
+        ease_factor,
+        interval_days,
+        repetitions: schedule.repetitions + 1,
+    }
+}
+
+/// Bookmarked segments due for review across all audios of the user.
+#[tauri::command]
+#[specta::specta]
+pub async fn get_due_reviews(
+    app_handle: AppHandle,
+    state: tauri::State<'_, DbState>,
+    limit: Option<i64>,
+) -> Result<Vec<ReviewCard>, AppError> {
+    let db = &state.db;
+
+    let user = require_user(&app_handle, db).await?;
+
+    Ok(get_due_cards(db, &user.user_id, limit.unwrap_or(DEFAULT_REVIEW_LIMIT)).await?)
+}
+
+/// Grades a review from 0 (forgot) to 5 (perfect recall) and reschedules the card.
+#[tauri::command]
+#[specta::specta]
+pub async fn grade_review(
+    app_handle: AppHandle,
+    state: tauri::State<'_, DbState>,
+    card_id: i64,
+    grade: u8,
+) -> Result<ReviewCard, AppError> {
+    let db = &state.db;
+
+    let user = require_user(&app_handle, db).await?;
+
+    if grade > 5 {
+        return Err(AppError::Validation(format!(
+            "Invalid grade {}, expected 0 to 5",
+            grade
+        )));
+    }
+
+    let card = get_review_card(db, &user.user_id, card_id)
+        .await?
+        .ok_or(AppError::NotFound(format!(
+            "Review card {} does not exist",
+            card_id
+        )))?;
+
+    let schedule = next_schedule(ReviewSchedule::from(&card), grade);
+
+    update_review_schedule(db, &user.user_id, card_id, &schedule).await?;
+
+    get_review_card(db, &user.user_id, card_id)
+        .await?
+        .ok_or(AppError::NotFound(format!(
+            "Review card {} does not exist",
+            card_id
+        )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NEW_CARD: ReviewSchedule = ReviewSchedule {
+        ease_factor: 2.5,
+        interval_days: 0,
+        repetitions: 0,
+    };
+
+    #[test]
+    fn intervals_grow_with_good_grades() {
+        let first = next_schedule(NEW_CARD, 4);
+        let second = next_schedule(first, 4);
+        let third = next_schedule(second, 4);
+
+        assert_eq!(
+            [
+                first.interval_days,
+                second.interval_days,
+                third.interval_days
+            ],
+            [1, 6, 15]
+        );
+        assert_eq!(third.repetitions, 3);
+        assert_eq!(third.ease_factor, 2.5);
+    }
+
+    #[test]
+    fn ease_follows_grade() {
+        assert!((next_schedule(NEW_CARD, 5).ease_factor - 2.6).abs() < 1e-9);
+        assert!((next_schedule(NEW_CARD, 3).ease_factor - 2.36).abs() < 1e-9);
+
+        let hard = ReviewSchedule {
+            ease_factor: 1.35,
+            ..NEW_CARD
+        };
+        assert_eq!(next_schedule(hard, 0).ease_factor, MIN_EASE_FACTOR);
+    }
+
+    #[test]
+    fn lapse_starts_over() {
+        let learned = ReviewSchedule {
+            ease_factor: 2.5,
+            interval_days: 30,
+            repetitions: 5,
+        };
+        let lapsed = next_schedule(learned, 2);
+
+        assert_eq!((lapsed.interval_days, lapsed.repetitions), (1, 0));
+        assert!(lapsed.ease_factor < learned.ease_factor);
+    }
+}
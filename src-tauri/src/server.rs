@@ -1,22 +1,187 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
 use serde::Serialize;
 use tauri::AppHandle;
 use tauri::Emitter;
+use tauri::Manager;
 use tauri::Url;
 use tauri_plugin_oauth::cancel;
 
 use tauri_plugin_oauth::start_with_config;
 use tauri_plugin_oauth::OauthConfig;
 
+use crate::auth::{bind_session, require_user};
+use crate::db::Db;
+use crate::error::AppError;
+use crate::query::oauth::handle_google_auth;
+use crate::query::user::CurrentUser;
+use crate::service::google::{random_state, GoogleOAuthClient, Pkce};
+use crate::DbState;
+
 #[derive(Serialize, Clone, specta::Type)]
 #[serde(tag = "type")] // This makes the variant name appear as "type"
 enum OAuthState {
-    Verify { code: String },
+    SignedIn { user: CurrentUser },
     Error { message: String },
 }
 
+/// Where the frontend sends the browser to sign in, the loopback server on
+/// `port` receives the redirect.
+#[derive(Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct OAuthStart {
+    pub port: u16,
+    pub auth_url: String,
+}
+
+// How long a sign in waits for its redirect, abandoned ones are dropped after it
+const PENDING_SIGN_IN_TTL: Duration = Duration::from_secs(10 * 60);
+
+struct PendingSignIn {
+    verifier: String,
+    redirect_uri: String,
+    started_at: Instant,
+}
+
+impl PendingSignIn {
+    fn is_stale(&self) -> bool {
+        self.started_at.elapsed() > PENDING_SIGN_IN_TTL
+    }
+}
+
+/// Sign ins waiting for their redirect, by `state`.
+#[derive(Default)]
+pub struct PendingSignIns(Mutex<HashMap<String, PendingSignIn>>);
+
+impl PendingSignIns {
+    fn insert(&self, state: String, sign_in: PendingSignIn) {
+        let mut pending = self.0.lock().unwrap();
+        pending.retain(|_, sign_in| !sign_in.is_stale());
+        pending.insert(state, sign_in);
+    }
+
+    fn take(&self, state: &str) -> Option<PendingSignIn> {
+        self.0
+            .lock()
+            .unwrap()
+            .remove(state)
+            .filter(|sign_in| !sign_in.is_stale())
+    }
+
+    fn clear(&self) {
+        self.0.lock().unwrap().clear();
+    }
+}
+
+/// Exchanges the code, verifies the ID token and only then signs the Google
+/// user in. Returns the new session token.
+async fn sign_in(
+    db: &Db,
+    client: &GoogleOAuthClient,
+    pending: &PendingSignIn,
+    code: &str,
+) -> Result<String, AppError> {
+    let tokens = client
+        .exchange_code(code, &pending.verifier, &pending.redirect_uri)
+        .await
+        .map_err(|e| AppError::Backend(e.to_string()))?;
+
+    let claims = client
+        .verify_id_token(&tokens.id_token)
+        .await
+        .map_err(|e| AppError::Unauthorized(format!("Invalid ID token: {}", e)))?;
+
+    let now = Utc::now().timestamp();
+
+    Ok(handle_google_auth(
+        db,
+        claims.sub,
+        claims.email,
+        claims.name.unwrap_or_default(),
+        claims.picture,
+        claims.email_verified,
+        Some(tokens.access_token),
+        tokens.expires_in.map(|seconds| now + seconds),
+        tokens.refresh_token,
+        tokens.refresh_token_expires_in.map(|seconds| now + seconds),
+    )
+    .await?)
+}
+
+async fn complete_sign_in(
+    app_handle: &AppHandle,
+    pending: PendingSignIn,
+    code: String,
+) -> Result<CurrentUser, AppError> {
+    let state = app_handle.state::<DbState>();
+    let client = app_handle.state::<GoogleOAuthClient>();
+    let db = &state.db;
+
+    let session_token = sign_in(db, &client, &pending, &code).await?;
+    bind_session(app_handle, &session_token)?;
+
+    Ok(require_user(app_handle, db).await?.into())
+}
+
+fn handle_redirect(app_handle: &AppHandle, url: &str) {
+    let emit_error = |message: String| {
+        let _ = app_handle.emit("oauth_code_state", OAuthState::Error { message });
+    };
+
+    let Ok(parsed_url) = Url::parse(url) else {
+        return;
+    };
+    let query_params: HashMap<_, _> = parsed_url.query_pairs().into_owned().collect();
+
+    let (code, error) = (query_params.get("code"), query_params.get("error"));
+    if code.is_none() && error.is_none() {
+        // Not the redirect, e.g. the browser asking for a favicon
+        return;
+    }
+
+    // The state is single use, whatever the outcome
+    let pending = query_params
+        .get("state")
+        .and_then(|state| app_handle.state::<PendingSignIns>().take(state));
+
+    if let Some(error) = error {
+        emit_error(format!("Sign in failed: {}", error));
+        return;
+    }
+
+    let (Some(pending), Some(code)) = (pending, code) else {
+        emit_error("Invalid Request".to_string());
+        return;
+    };
+
+    let app_handle = app_handle.clone();
+    let code = code.clone();
+    tauri::async_runtime::spawn(async move {
+        let event = match complete_sign_in(&app_handle, pending, code).await {
+            Ok(user) => OAuthState::SignedIn { user },
+            Err(e) => {
+                println!("❌ Failed to sign in: {}", e);
+                OAuthState::Error {
+                    message: e.message().to_string(),
+                }
+            }
+        };
+        let _ = app_handle.emit("oauth_code_state", event);
+    });
+}
+
+/// Starts a Google sign in with PKCE. The result comes as an
+/// `oauth_code_state` event once the browser is redirected back.
 #[tauri::command]
 #[specta::specta]
-pub async fn start_oauth_server(app_handle: AppHandle, state: String) -> Result<u16, String> {
+pub async fn start_oauth_server(
+    app_handle: AppHandle,
+    client: tauri::State<'_, GoogleOAuthClient>,
+    pending: tauri::State<'_, PendingSignIns>,
+) -> Result<OAuthStart, AppError> {
     let close_res = r#"<html>
 <head>
     <title>Authentication Complete</title>
@@ -36,44 +201,128 @@ pub async fn start_oauth_server(app_handle: AppHandle, state: String) -> Result<
 </body>
 </html>"#;
     let config = OauthConfig {
-        ports: Some(vec![8001]),          // Try port 8000 first, then 8001
+        ports: Some(vec![8001]),          // Only port 8001
         response: Some(close_res.into()), // Use default response"
     };
 
-    let app_handle = app_handle.clone();
-    start_with_config(config, move |url| {
-        let parsed_url = Url::parse(&url).unwrap();
-        let query_params: std::collections::HashMap<_, _> =
-            parsed_url.query_pairs().into_owned().collect();
-
-        if let Some(state_query) = query_params.get("state") {
-            if *state_query != state {
-                let _ = app_handle.emit(
-                    "oauth_code_state",
-                    OAuthState::Error {
-                        message: "Invalid Request".to_string(),
-                    },
-                );
-            }
-        };
+    let state = random_state();
+    let pkce = Pkce::new();
 
-        if let Some(code_query) = query_params.get("code") {
-            let _ = app_handle.emit(
-                "oauth_code_state",
-                OAuthState::Verify {
-                    code: code_query.clone(),
-                },
-            );
-        }
-    })
-    .map_err(|err| err.to_string())
+    let callback_handle = app_handle.clone();
+    let port = start_with_config(config, move |url| handle_redirect(&callback_handle, &url))
+        .map_err(|err| AppError::Backend(err.to_string()))?;
+
+    let redirect_uri = format!("http://localhost:{}", port);
+    let auth_url = client
+        .authorization_url(&redirect_uri, &state, &pkce)
+        .map_err(|e| AppError::Backend(e.to_string()))?;
+
+    pending.insert(
+        state,
+        PendingSignIn {
+            verifier: pkce.verifier,
+            redirect_uri,
+            started_at: Instant::now(),
+        },
+    );
+
+    Ok(OAuthStart { port, auth_url })
 }
 
 #[tauri::command]
 #[specta::specta]
-pub fn stop_oauth_server(port: u16) -> Result<String, String> {
+pub fn stop_oauth_server(
+    pending: tauri::State<'_, PendingSignIns>,
+    port: u16,
+) -> Result<String, AppError> {
+    pending.clear();
+
     match cancel(port) {
         Ok(()) => Ok(format!("Closed server on (port: {}) successfully", port)),
-        Err(_) => Err(format!("Can't closed server on (port: {})", port)),
+        Err(_) => Err(AppError::Backend(format!(
+            "Can't closed server on (port: {})",
+            port
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        db::test_db,
+        query::user::get_user_by_session_token,
+        service::google::tests::{id_token, MockProvider},
+    };
+
+    fn pending() -> PendingSignIn {
+        PendingSignIn {
+            verifier: Pkce::new().verifier,
+            redirect_uri: "http://localhost:8001".to_string(),
+            started_at: Instant::now(),
+        }
+    }
+
+    #[test]
+    fn drops_stale_sign_ins() {
+        let pending_sign_ins = PendingSignIns::default();
+        let stale = || PendingSignIn {
+            started_at: Instant::now() - PENDING_SIGN_IN_TTL - Duration::from_secs(1),
+            ..pending()
+        };
+
+        pending_sign_ins.insert("abandoned".to_string(), stale());
+        pending_sign_ins.insert("late".to_string(), stale());
+        assert!(pending_sign_ins.take("late").is_none());
+
+        pending_sign_ins.insert("current".to_string(), pending());
+        assert_eq!(pending_sign_ins.0.lock().unwrap().len(), 1);
+        assert!(pending_sign_ins.take("current").is_some());
+    }
+
+    #[tokio::test]
+    async fn signs_in_with_a_verified_id_token() {
+        let db = test_db().await;
+        let mock = MockProvider::start(id_token(json!({}))).await;
+        let client = GoogleOAuthClient::new(mock.config.clone());
+
+        let token = sign_in(&db, &client, &pending(), "the-code").await.unwrap();
+
+        let user: CurrentUser = get_user_by_session_token(&db, token)
+            .await
+            .unwrap()
+            .unwrap()
+            .into();
+        assert_eq!(user.email, "ann@example.com");
+        assert_eq!(user.name, "Ann");
+
+        let account: (String, Option<String>) =
+            sqlx::query_as("SELECT accountId, refreshToken FROM account")
+                .fetch_one(&db)
+                .await
+                .unwrap();
+        assert_eq!(
+            account,
+            ("1234567890".to_string(), Some("refresh".to_string()))
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_an_id_token_for_another_client() {
+        let db = test_db().await;
+        let forged = id_token(json!({ "aud": "someone-else.apps.googleusercontent.com" }));
+        let mock = MockProvider::start(forged).await;
+        let client = GoogleOAuthClient::new(mock.config.clone());
+
+        let result = sign_in(&db, &client, &pending(), "the-code").await;
+
+        assert!(matches!(result, Err(AppError::Unauthorized(_))));
+        let users: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM user")
+            .fetch_one(&db)
+            .await
+            .unwrap();
+        assert_eq!(users, 0);
     }
 }
@@ -0,0 +1,497 @@
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use jsonwebtoken::{decode, decode_header, jwk::JwkSet, Algorithm, DecodingKey, Validation};
+use rand::{distributions::Alphanumeric, Rng};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use tauri::Url;
+use tokio::sync::Mutex;
+
+const AUTH_ENDPOINT: &str = "https://accounts.google.com/o/oauth2/v2/auth";
+const TOKEN_ENDPOINT: &str = "https://oauth2.googleapis.com/token";
+const JWKS_URI: &str = "https://www.googleapis.com/oauth2/v3/certs";
+const ISSUERS: [&str; 2] = ["https://accounts.google.com", "accounts.google.com"];
+const SCOPE: &str = "openid email profile";
+/// How long the signing keys are cached when the response has no `max-age`
+const DEFAULT_JWKS_MAX_AGE: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug, Clone)]
+pub struct GoogleOAuthConfig {
+    pub client_id: String,
+    /// Google requires it for desktop clients, even with PKCE
+    pub client_secret: Option<String>,
+    pub auth_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+    pub issuers: Vec<String>,
+}
+
+fn env_or_build(name: &str, build_value: Option<&str>) -> Option<String> {
+    std::env::var(name)
+        .ok()
+        .or(build_value.map(str::to_string))
+        .filter(|value| !value.is_empty())
+}
+
+/// `default`, or the endpoint from the `name` variable in debug builds. A
+/// release build always talks to Google and checks tokens against its keys.
+#[cfg(debug_assertions)]
+fn endpoint(name: &str, default: &str) -> String {
+    env_or_build(name, None).unwrap_or(default.to_string())
+}
+
+#[cfg(not(debug_assertions))]
+fn endpoint(_name: &str, default: &str) -> String {
+    default.to_string()
+}
+
+impl GoogleOAuthConfig {
+    /// Google's endpoints with the client from `GOOGLE_CLIENT_ID` and
+    /// `GOOGLE_CLIENT_SECRET`, read at runtime or else at build time.
+    /// In debug builds `GOOGLE_OAUTH_TOKEN_ENDPOINT` and `GOOGLE_OAUTH_JWKS_URI`
+    /// point the exchange and the key lookup elsewhere, e.g. at a mock server.
+    pub fn from_env() -> Self {
+        Self {
+            client_id: env_or_build("GOOGLE_CLIENT_ID", option_env!("GOOGLE_CLIENT_ID"))
+                .unwrap_or_default(),
+            client_secret: env_or_build(
+                "GOOGLE_CLIENT_SECRET",
+                option_env!("GOOGLE_CLIENT_SECRET"),
+            ),
+            auth_endpoint: AUTH_ENDPOINT.to_string(),
+            token_endpoint: endpoint("GOOGLE_OAUTH_TOKEN_ENDPOINT", TOKEN_ENDPOINT),
+            jwks_uri: endpoint("GOOGLE_OAUTH_JWKS_URI", JWKS_URI),
+            issuers: ISSUERS.iter().map(|issuer| issuer.to_string()).collect(),
+        }
+    }
+}
+
+/// A PKCE code verifier and its S256 challenge.
+#[derive(Debug, Clone)]
+pub struct Pkce {
+    pub verifier: String,
+    pub challenge: String,
+}
+
+impl Pkce {
+    pub fn new() -> Self {
+        let verifier: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(64)
+            .map(char::from)
+            .collect();
+
+        Self {
+            challenge: pkce_challenge(&verifier),
+            verifier,
+        }
+    }
+}
+
+pub fn pkce_challenge(verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()))
+}
+
+/// An unguessable `state` tying the redirect to the sign in that started it.
+pub fn random_state() -> String {
+    let bytes: [u8; 32] = rand::thread_rng().gen();
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TokenResponse {
+    pub access_token: String,
+    /// Seconds
+    pub expires_in: Option<i64>,
+    pub id_token: String,
+    pub refresh_token: Option<String>,
+    /// Seconds
+    pub refresh_token_expires_in: Option<i64>,
+}
+
+/// The claims of a verified ID token the app uses.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct IdTokenClaims {
+    pub sub: String,
+    pub email: String,
+    #[serde(default)]
+    pub email_verified: bool,
+    pub name: Option<String>,
+    pub picture: Option<String>,
+}
+
+struct CachedJwks {
+    keys: JwkSet,
+    fetched_at: Instant,
+    max_age: Duration,
+}
+
+impl CachedJwks {
+    fn is_fresh(&self) -> bool {
+        self.fetched_at.elapsed() < self.max_age
+    }
+}
+
+/// Reads `max-age` out of a `Cache-Control` header.
+fn max_age(cache_control: &str) -> Option<Duration> {
+    cache_control
+        .split(',')
+        .find_map(|directive| directive.trim().strip_prefix("max-age="))
+        .and_then(|seconds| seconds.parse().ok())
+        .map(Duration::from_secs)
+}
+
+pub struct GoogleOAuthClient {
+    config: GoogleOAuthConfig,
+    client: reqwest::Client,
+    jwks: Mutex<Option<CachedJwks>>,
+}
+
+impl GoogleOAuthClient {
+    pub fn new(config: GoogleOAuthConfig) -> Self {
+        Self {
+            config,
+            client: reqwest::Client::new(),
+            jwks: Mutex::new(None),
+        }
+    }
+
+    /// The consent page URL the browser is sent to.
+    pub fn authorization_url(
+        &self,
+        redirect_uri: &str,
+        state: &str,
+        pkce: &Pkce,
+    ) -> Result<String> {
+        let mut url = Url::parse(&self.config.auth_endpoint)?;
+        url.query_pairs_mut()
+            .append_pair("client_id", &self.config.client_id)
+            .append_pair("redirect_uri", redirect_uri)
+            .append_pair("response_type", "code")
+            .append_pair("scope", SCOPE)
+            .append_pair("code_challenge", &pkce.challenge)
+            .append_pair("code_challenge_method", "S256")
+            .append_pair("state", state);
+
+        Ok(url.to_string())
+    }
+
+    /// Exchanges the authorization code from the redirect for tokens.
+    pub async fn exchange_code(
+        &self,
+        code: &str,
+        verifier: &str,
+        redirect_uri: &str,
+    ) -> Result<TokenResponse> {
+        let mut form = vec![
+            ("client_id", self.config.client_id.as_str()),
+            ("code", code),
+            ("code_verifier", verifier),
+            ("grant_type", "authorization_code"),
+            ("redirect_uri", redirect_uri),
+        ];
+        if let Some(client_secret) = &self.config.client_secret {
+            form.push(("client_secret", client_secret));
+        }
+
+        let response = self
+            .client
+            .post(&self.config.token_endpoint)
+            .form(&form)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await?;
+            return Err(anyhow!(
+                "Failed to exchange the code for tokens: {} {}",
+                status,
+                error_text
+            ));
+        }
+
+        Ok(response.json().await?)
+    }
+
+    async fn fetch_jwks(&self) -> Result<CachedJwks> {
+        let response = self.client.get(&self.config.jwks_uri).send().await?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!(
+                "Failed to fetch signing keys: {}",
+                response.status()
+            ));
+        }
+
+        let max_age = response
+            .headers()
+            .get(reqwest::header::CACHE_CONTROL)
+            .and_then(|value| value.to_str().ok())
+            .and_then(max_age)
+            .unwrap_or(DEFAULT_JWKS_MAX_AGE);
+
+        Ok(CachedJwks {
+            keys: response.json().await?,
+            fetched_at: Instant::now(),
+            max_age,
+        })
+    }
+
+    /// The signing key `kid`, refetching the keys once they are stale or
+    /// when the key is new (Google rotates them).
+    async fn decoding_key(&self, kid: &str) -> Result<DecodingKey> {
+        let mut jwks = self.jwks.lock().await;
+
+        let cached = jwks
+            .as_ref()
+            .filter(|cached| cached.is_fresh())
+            .and_then(|cached| cached.keys.find(kid).cloned());
+
+        let jwk = match cached {
+            Some(jwk) => jwk,
+            None => {
+                let fetched = self.fetch_jwks().await?;
+                let jwk = fetched.keys.find(kid).cloned();
+                *jwks = Some(fetched);
+                jwk.ok_or(anyhow!("Unknown signing key: {}", kid))?
+            }
+        };
+
+        Ok(DecodingKey::from_jwk(&jwk)?)
+    }
+
+    /// Checks the signature, issuer, audience and expiry of an ID token.
+    pub async fn verify_id_token(&self, id_token: &str) -> Result<IdTokenClaims> {
+        let header = decode_header(id_token)?;
+        if header.alg != Algorithm::RS256 {
+            return Err(anyhow!("Unexpected ID token algorithm: {:?}", header.alg));
+        }
+        let kid = header.kid.ok_or(anyhow!("The ID token has no key id"))?;
+
+        let key = self.decoding_key(&kid).await?;
+
+        let mut validation = Validation::new(Algorithm::RS256);
+        validation.set_audience(&[&self.config.client_id]);
+        validation.set_issuer(&self.config.issuers);
+
+        Ok(decode::<IdTokenClaims>(id_token, &key, &validation)?.claims)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::sync::Arc;
+
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    use serde_json::{json, Value};
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    use super::*;
+
+    pub(crate) const CLIENT_ID: &str = "attune-test.apps.googleusercontent.com";
+    const KEY_ID: &str = "test-key";
+    // Signs the mock ID tokens, a key generated for these tests only
+    const PRIVATE_KEY: &str = include_str!("../../tests/fixtures/google_oauth_test_key.pem");
+    // The public modulus of `PRIVATE_KEY`, published by the mock key set
+    const MODULUS: &str = "x1i32r0830Sb5Zt1wImCH4W6IX-tztucjlYEa4PpkBOPSIYw_EvZ-ldkhLO24oZFcARb_JH1q-FOgmd0PCN8TE4wIuP6j2mPRWJHidje4yhP1TM2Jr1fE2Na9mgpmxd6aJatC_ipbCHMsaoTYMjOXuhajHgFl_ge5f-N9h5GBv7PdlU32iY1mDKBegZGlLqzzjZCQaKldm3ow7V4sqdS7l9zVfZJz0X3SVM5hrp4H98syTvGx-g7A0MrITF48lrzYOlvgAfsPOOk493_MI7n0IJCmFkmVPGTFil5c7Wm8FXFRoMB6q7hYh5BFFWzt-6aT1FNEjSypW-F9nRVvXat0Q";
+
+    /// A token endpoint and key set on localhost, recording the token requests.
+    pub(crate) struct MockProvider {
+        pub(crate) config: GoogleOAuthConfig,
+        pub(crate) token_requests: Arc<std::sync::Mutex<Vec<String>>>,
+    }
+
+    impl MockProvider {
+        pub(crate) async fn start(id_token: String) -> Self {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let base_url = format!("http://{}", listener.local_addr().unwrap());
+            let token_requests = Arc::new(std::sync::Mutex::new(Vec::new()));
+            let requests = token_requests.clone();
+
+            let token_response = json!({
+                "access_token": "access",
+                "expires_in": 3599,
+                "id_token": id_token,
+                "refresh_token": "refresh",
+                "token_type": "Bearer",
+            });
+            let jwks = json!({
+                "keys": [{
+                    "kty": "RSA",
+                    "kid": KEY_ID,
+                    "use": "sig",
+                    "alg": "RS256",
+                    "n": MODULUS,
+                    "e": "AQAB",
+                }]
+            });
+
+            tokio::spawn(async move {
+                loop {
+                    let (mut stream, _) = listener.accept().await.unwrap();
+                    let request = read_request(&mut stream).await;
+
+                    let body = if request.starts_with("POST /token") {
+                        let form = request.split("\r\n\r\n").nth(1).unwrap_or_default();
+                        requests.lock().unwrap().push(form.to_string());
+                        token_response.to_string()
+                    } else {
+                        jwks.to_string()
+                    };
+
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nCache-Control: public, max-age=600\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        body.len(),
+                        body
+                    );
+                    let _ = stream.write_all(response.as_bytes()).await;
+                }
+            });
+
+            Self {
+                config: GoogleOAuthConfig {
+                    client_id: CLIENT_ID.to_string(),
+                    client_secret: None,
+                    auth_endpoint: format!("{}/auth", base_url),
+                    token_endpoint: format!("{}/token", base_url),
+                    jwks_uri: format!("{}/jwks", base_url),
+                    issuers: ISSUERS.iter().map(|issuer| issuer.to_string()).collect(),
+                },
+                token_requests,
+            }
+        }
+    }
+
+    async fn read_request(stream: &mut tokio::net::TcpStream) -> String {
+        let mut request = Vec::new();
+        let mut buffer = [0; 4096];
+
+        loop {
+            let read = stream.read(&mut buffer).await.unwrap();
+            request.extend_from_slice(&buffer[..read]);
+            let text = String::from_utf8_lossy(&request).to_string();
+
+            if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                let length = head
+                    .lines()
+                    .find_map(|line| {
+                        line.to_lowercase()
+                            .strip_prefix("content-length: ")
+                            .map(str::to_string)
+                    })
+                    .and_then(|length| length.trim().parse().ok())
+                    .unwrap_or(0);
+                if body.len() >= length {
+                    return text;
+                }
+            }
+            if read == 0 {
+                return text;
+            }
+        }
+    }
+
+    /// An ID token signed with the mock provider's key.
+    pub(crate) fn id_token(overrides: Value) -> String {
+        let now = chrono::Utc::now().timestamp();
+        let mut claims = json!({
+            "iss": "https://accounts.google.com",
+            "aud": CLIENT_ID,
+            "sub": "1234567890",
+            "email": "ann@example.com",
+            "email_verified": true,
+            "name": "Ann",
+            "picture": "https://example.com/ann.png",
+            "iat": now,
+            "exp": now + 3600,
+        });
+        for (key, value) in overrides.as_object().unwrap() {
+            claims[key] = value.clone();
+        }
+
+        let mut header = Header::new(Algorithm::RS256);
+        header.kid = Some(KEY_ID.to_string());
+
+        encode(
+            &header,
+            &claims,
+            &EncodingKey::from_rsa_pem(PRIVATE_KEY.as_bytes()).unwrap(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn challenge_matches_rfc_7636() {
+        assert_eq!(
+            pkce_challenge("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"),
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"
+        );
+        let pkce = Pkce::new();
+        assert_eq!(pkce.challenge, pkce_challenge(&pkce.verifier));
+    }
+
+    #[test]
+    fn reads_max_age() {
+        assert_eq!(
+            max_age("public, max-age=22173, must-revalidate, no-transform"),
+            Some(Duration::from_secs(22173))
+        );
+        assert_eq!(max_age("no-cache"), None);
+    }
+
+    #[tokio::test]
+    async fn exchanges_the_code_and_verifies_the_id_token() {
+        let mock = MockProvider::start(id_token(json!({}))).await;
+        let client = GoogleOAuthClient::new(mock.config.clone());
+
+        let tokens = client
+            .exchange_code("the-code", "the-verifier", "http://localhost:8001")
+            .await
+            .unwrap();
+        let claims = client.verify_id_token(&tokens.id_token).await.unwrap();
+
+        assert_eq!(claims.sub, "1234567890");
+        assert!(claims.email_verified);
+        assert_eq!(tokens.refresh_token.as_deref(), Some("refresh"));
+
+        let form = &mock.token_requests.lock().unwrap()[0];
+        assert!(form.contains("code=the-code"));
+        assert!(form.contains("code_verifier=the-verifier"));
+        assert!(form.contains("grant_type=authorization_code"));
+    }
+
+    #[tokio::test]
+    async fn rejects_tokens_for_other_clients_or_issuers() {
+        let mock = MockProvider::start(String::new()).await;
+        let client = GoogleOAuthClient::new(mock.config.clone());
+
+        for overrides in [
+            json!({ "aud": "someone-else.apps.googleusercontent.com" }),
+            json!({ "iss": "https://evil.example.com" }),
+            json!({ "exp": chrono::Utc::now().timestamp() - 3600 }),
+        ] {
+            assert!(client.verify_id_token(&id_token(overrides)).await.is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_tampered_tokens() {
+        let mock = MockProvider::start(String::new()).await;
+        let client = GoogleOAuthClient::new(mock.config.clone());
+
+        let token = id_token(json!({}));
+        let forged = id_token(json!({ "sub": "someone-else" }));
+        // The claims of one token with the signature of the other
+        let parts: Vec<&str> = token.split('.').collect();
+        let forged_parts: Vec<&str> = forged.split('.').collect();
+        let tampered = format!("{}.{}.{}", parts[0], forged_parts[1], parts[2]);
+
+        assert!(client.verify_id_token(&tampered).await.is_err());
+    }
+}
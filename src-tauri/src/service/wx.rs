@@ -11,11 +11,23 @@ pub struct TranscriptionResponse {
     pub language: String,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, specta::Type)]
+pub struct Word {
+    pub word: String,
+    // WhisperX can't align some tokens (e.g. numbers), those come without timing
+    pub start: Option<f64>,
+    pub end: Option<f64>,
+    pub score: Option<f64>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, specta::Type)]
 pub struct Segment {
     pub start: f64,
     pub end: f64,
     pub text: String,
+    /// Word-level timings, only present when the transcript was aligned
+    #[serde(default)]
+    pub words: Option<Vec<Word>>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, specta::Type)]
@@ -164,67 +176,6 @@ impl WhisperXClient {
         final_result.ok_or_else(|| anyhow::anyhow!("No final result received"))
     }
 
-    /// Original non-streaming transcribe method (unchanged)
-    pub async fn transcribe<P: AsRef<Path>>(
-        &self,
-        file_path: P,
-        model: Option<&str>,
-        lang: Option<&str>,
-        model_path: Option<&str>,
-        output_dir: Option<&str>,
-        initial_prompt: Option<&str>,
-    ) -> Result<TranscriptionResponse> {
-        let file_path = file_path.as_ref();
-
-        // Read the file
-        let file_bytes = tokio::fs::read(file_path).await?;
-        let filename = file_path
-            .file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or("audio.wav");
-
-        // Create multipart form
-        let mut form = reqwest::multipart::Form::new().part(
-            "file",
-            reqwest::multipart::Part::bytes(file_bytes)
-                .file_name(filename.to_string())
-                .mime_str("audio/wav")?,
-        );
-
-        // Add optional parameters
-        if let Some(model) = model {
-            form = form.text("model", model.to_string());
-        }
-        if let Some(lang) = lang {
-            form = form.text("lang", lang.to_string());
-        }
-        if let Some(model_path) = model_path {
-            form = form.text("model_path", model_path.to_string());
-        }
-        if let Some(output_dir) = output_dir {
-            form = form.text("output_dir", output_dir.to_string());
-        }
-        if let Some(initial_prompt) = initial_prompt {
-            form = form.text("initial_prompt", initial_prompt.to_string());
-        }
-
-        // Send the request to sync endpoint
-        let response = self
-            .client
-            .post(&format!("{}/transcribe-sync", self.base_url))
-            .multipart(form)
-            .send()
-            .await?;
-
-        if response.status().is_success() {
-            let transcription: TranscriptionResponse = response.json().await?;
-            Ok(transcription)
-        } else {
-            let error_text = response.text().await?;
-            Err(anyhow::anyhow!("Request failed: {}", error_text))
-        }
-    }
-
     /// Health check
     pub async fn health_check(&self) -> Result<bool> {
         let response = self
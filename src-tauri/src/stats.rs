@@ -0,0 +1,218 @@
+use std::collections::BTreeMap;
+
+use chrono::{Days, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::{
+    auth::require_user,
+    error::AppError,
+    query::stats::{
+        get_attempt_stats, get_created_counts, get_most_missed_words, get_practice_days, MissedWord,
+    },
+    DbState,
+};
+
+const MOST_MISSED_WORDS: i64 = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "lowercase")]
+pub enum StatsRange {
+    /// The last 7 days, by day
+    Week,
+    /// The last 30 days, by day
+    Month,
+    /// The last 52 weeks, by week
+    Year,
+    /// Everything, by week
+    All,
+}
+
+impl StatsRange {
+    fn days(&self) -> Option<u64> {
+        match self {
+            StatsRange::Week => Some(7),
+            StatsRange::Month => Some(30),
+            StatsRange::Year => Some(52 * 7),
+            StatsRange::All => None,
+        }
+    }
+
+    /// SQL expression turning a `day` column into the first day of its period.
+    pub(crate) fn period(&self) -> &'static str {
+        match self {
+            StatsRange::Week | StatsRange::Month => "day",
+            // The Monday of the week
+            StatsRange::Year | StatsRange::All => "date(day, 'weekday 0', '-6 days')",
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct PracticePeriod {
+    /// First day of the day/week, `YYYY-MM-DD`
+    pub start: String,
+    pub minutes_practiced: f64,
+    pub segments_dictated: i64,
+    pub attempts: i64,
+    pub average_accuracy: Option<f64>,
+    pub bookmarks_added: i64,
+    pub audios_added: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct PracticeStats {
+    pub range: StatsRange,
+    pub periods: Vec<PracticePeriod>,
+    pub minutes_practiced: f64,
+    pub segments_dictated: i64,
+    pub attempts: i64,
+    pub average_accuracy: Option<f64>,
+    pub most_missed_words: Vec<MissedWord>,
+    /// Days in a row with practice up to today (or yesterday, until the user practices today)
+    pub current_streak: i64,
+    pub longest_streak: i64,
+}
+
+/// Current and longest run of consecutive days in `days` (ascending, distinct).
+pub(crate) fn streaks(days: &[NaiveDate], today: NaiveDate) -> (i64, i64) {
+    let mut longest = 0;
+    let mut run = 0;
+    let mut previous: Option<NaiveDate> = None;
+
+    for day in days {
+        run = match previous {
+            Some(previous) if previous.succ_opt() == Some(*day) => run + 1,
+            _ => 1,
+        };
+        longest = longest.max(run);
+        previous = Some(*day);
+    }
+
+    let current = match previous {
+        Some(last) if last == today || last.succ_opt() == Some(today) => run,
+        _ => 0,
+    };
+
+    (current, longest)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_practice_stats(
+    app_handle: AppHandle,
+    state: tauri::State<'_, DbState>,
+    range: StatsRange,
+) -> Result<PracticeStats, AppError> {
+    let db = &state.db;
+
+    let user = require_user(&app_handle, db).await?;
+
+    let today = Local::now().date_naive();
+    let since = range
+        .days()
+        .and_then(|days| today.checked_sub_days(Days::new(days - 1)))
+        .map(|day| day.to_string());
+    let since = since.as_deref();
+    let period = range.period();
+
+    let mut periods: BTreeMap<String, PracticePeriod> = BTreeMap::new();
+
+    let attempt_periods = get_attempt_stats(db, &user.user_id, since, Some(period)).await?;
+    for stats in attempt_periods {
+        let start = stats.period.unwrap_or_default();
+        periods.insert(
+            start.clone(),
+            PracticePeriod {
+                start,
+                minutes_practiced: stats.minutes_practiced,
+                segments_dictated: stats.segments_dictated,
+                attempts: stats.attempts,
+                average_accuracy: stats.average_accuracy,
+                ..Default::default()
+            },
+        );
+    }
+
+    for table in ["bookmark", "audio"] {
+        let counts = get_created_counts(db, table, &user.user_id, since, period).await?;
+
+        for (start, count) in counts {
+            let entry = periods.entry(start.clone()).or_insert(PracticePeriod {
+                start,
+                ..Default::default()
+            });
+            if table == "bookmark" {
+                entry.bookmarks_added = count;
+            } else {
+                entry.audios_added = count;
+            }
+        }
+    }
+
+    let totals = get_attempt_stats(db, &user.user_id, since, None)
+        .await?
+        .pop()
+        .unwrap_or_default();
+
+    let most_missed_words =
+        get_most_missed_words(db, &user.user_id, since, MOST_MISSED_WORDS).await?;
+
+    let days: Vec<NaiveDate> = get_practice_days(db, &user.user_id)
+        .await?
+        .iter()
+        .filter_map(|day| day.parse().ok())
+        .collect();
+    let (current_streak, longest_streak) = streaks(&days, today);
+
+    Ok(PracticeStats {
+        range,
+        periods: periods.into_values().collect(),
+        minutes_practiced: totals.minutes_practiced,
+        segments_dictated: totals.segments_dictated,
+        attempts: totals.attempts,
+        average_accuracy: totals.average_accuracy,
+        most_missed_words,
+        current_streak,
+        longest_streak,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dates(days: &[&str]) -> Vec<NaiveDate> {
+        days.iter().map(|day| day.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn counts_streaks() {
+        let days = dates(&[
+            "2026-09-28",
+            "2026-09-29",
+            "2026-09-30",
+            "2026-10-01",
+            "2026-10-15",
+            "2026-10-16",
+        ]);
+        let today = "2026-10-16".parse().unwrap();
+
+        assert_eq!(streaks(&days, today), (2, 4));
+    }
+
+    #[test]
+    fn streak_holds_until_the_day_ends() {
+        let days = dates(&["2026-10-16", "2026-10-17"]);
+
+        assert_eq!(streaks(&days, "2026-10-18".parse().unwrap()), (2, 2));
+        assert_eq!(streaks(&days, "2026-10-19".parse().unwrap()), (0, 2));
+    }
+
+    #[test]
+    fn no_practice() {
+        assert_eq!(streaks(&[], "2026-10-18".parse().unwrap()), (0, 0));
+    }
+}
@@ -0,0 +1,749 @@
+use serde::{Deserialize, Serialize};
+
+use crate::service::wx::{Segment, Word};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "lowercase")]
+pub enum SubtitleFormat {
+    Srt,
+    Vtt,
+    Tsv,
+    Txt,
+}
+
+/// Parses `hh:mm:ss.ttt` / `mm:ss.ttt` (a `,` separator is accepted too) into seconds.
+fn parse_timestamp(value: &str) -> Option<f64> {
+    let value = value.trim().replace(',', ".");
+    let parts: Vec<&str> = value.split(':').collect();
+
+    let (hours, minutes, seconds) = match parts.as_slice() {
+        [h, m, s] => (h.parse::<f64>().ok()?, m.parse::<f64>().ok()?, s),
+        [m, s] => (0.0, m.parse::<f64>().ok()?, s),
+        _ => return None,
+    };
+    let seconds: f64 = seconds.parse().ok()?;
+
+    Some(hours * 3600.0 + minutes * 60.0 + seconds)
+}
+
+/// Drops `<...>` markup (voice spans, inline timestamps, styling) and decodes the common entities.
+fn strip_markup(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut in_tag = false;
+
+    for c in text.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => plain.push(c),
+            _ => {}
+        }
+    }
+
+    plain
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+fn normalize_text(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Drops ASS override blocks (`{\i1}`, `{\an8}`) and turns its line breaks into spaces.
+fn strip_ass_markup(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut in_block = false;
+
+    for c in text.chars() {
+        match c {
+            '{' => in_block = true,
+            '}' if in_block => in_block = false,
+            _ if !in_block => plain.push(c),
+            _ => {}
+        }
+    }
+
+    plain
+        .replace("\\N", " ")
+        .replace("\\n", " ")
+        .replace("\\h", " ")
+}
+
+/// Parses a SubRip file into segments with absolute times.
+pub fn parse_srt(content: &str) -> Vec<Segment> {
+    // SRT cues are WebVTT cues with a numeric identifier and a `,` before the
+    // milliseconds; some editors also leave ASS style overrides in the text
+    parse_vtt(&strip_ass_markup(content))
+}
+
+/// Parses the `Dialogue:` lines of an ASS/SSA file into segments with absolute times.
+pub fn parse_ass(content: &str) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut in_events = false;
+    let mut format: Vec<String> = Vec::new();
+
+    for line in content.lines() {
+        let line = line.trim();
+
+        if line.starts_with('[') {
+            in_events = line.eq_ignore_ascii_case("[events]");
+            continue;
+        }
+        if !in_events {
+            continue;
+        }
+
+        if let Some(fields) = line.strip_prefix("Format:") {
+            format = fields
+                .split(',')
+                .map(|field| field.trim().to_lowercase())
+                .collect();
+            continue;
+        }
+
+        let Some(dialogue) = line.strip_prefix("Dialogue:") else {
+            continue; // comments and other event types
+        };
+        if format.is_empty() {
+            continue;
+        }
+
+        // The text is the last field and can contain commas itself
+        let values: Vec<&str> = dialogue.splitn(format.len(), ',').collect();
+        let field = |name: &str| {
+            format
+                .iter()
+                .position(|field| field == name)
+                .and_then(|index| values.get(index))
+        };
+
+        let start = field("start").and_then(|value| parse_timestamp(value));
+        let end = field("end").and_then(|value| parse_timestamp(value));
+        let (Some(start), Some(end)) = (start, end) else {
+            continue;
+        };
+
+        let text = normalize_text(&strip_ass_markup(field("text").copied().unwrap_or("")));
+        if text.is_empty() || end <= start {
+            continue;
+        }
+
+        segments.push(Segment {
+            start,
+            end,
+            text,
+            words: None,
+        });
+    }
+
+    segments
+}
+
+/// Parses a subtitle file by its extension, or by its content when the
+/// extension isn't a known one.
+pub fn parse_subtitle_file(path: &str, content: &str) -> Result<Vec<Segment>, String> {
+    let content = content.trim_start_matches('\u{feff}');
+    let extension = path
+        .rsplit_once('.')
+        .map(|(_, extension)| extension.to_lowercase())
+        .unwrap_or_default();
+
+    let segments = match extension.as_str() {
+        "vtt" => parse_vtt(content),
+        "srt" => parse_srt(content),
+        "ass" | "ssa" => parse_ass(content),
+        _ if content.starts_with("WEBVTT") => parse_vtt(content),
+        _ if content.contains("[Events]") => parse_ass(content),
+        _ if content.contains("-->") => parse_srt(content),
+        _ => return Err(format!("Unsupported subtitle file: {}", path)),
+    };
+
+    Ok(normalize_segments(segments))
+}
+
+/// Sorts cues by time and resolves overlaps: cues starting together (e.g. the
+/// two lines of a bilingual ASS file) are merged, and a cue running into the
+/// next one is cut where the next one starts.
+pub fn normalize_segments(mut segments: Vec<Segment>) -> Vec<Segment> {
+    segments.sort_by(|a, b| a.start.total_cmp(&b.start));
+
+    let mut normalized: Vec<Segment> = Vec::with_capacity(segments.len());
+    for segment in segments {
+        match normalized.last_mut() {
+            Some(last) if last.start == segment.start => {
+                if last.text != segment.text {
+                    last.text = format!("{} {}", last.text, segment.text);
+                }
+                last.end = last.end.max(segment.end);
+            }
+            Some(last) if segment.start < last.end => {
+                last.end = segment.start;
+                normalized.push(segment);
+            }
+            _ => normalized.push(segment),
+        }
+    }
+
+    normalized
+}
+
+/// Fits imported subtitles to the `start`-`end` clip. Files covering the
+/// whole video (cues past the clip's length) are clipped and re-timed, files
+/// made for the clip itself are only cut at its length.
+pub fn align_to_clip(segments: Vec<Segment>, start: f64, end: f64) -> Vec<Segment> {
+    let duration = end - start;
+    // Allow the last cue to linger a little past the end of the clip
+    let whole_video = segments.iter().any(|segment| segment.end > duration + 1.0);
+
+    if whole_video {
+        clip_segments(segments, start, end)
+    } else {
+        clip_segments(segments, 0.0, duration)
+    }
+}
+
+/// Parses a WebVTT file into segments with absolute times.
+pub fn parse_vtt(content: &str) -> Vec<Segment> {
+    let content = content.replace("\r\n", "\n");
+    let mut segments = Vec::new();
+
+    for block in content.split("\n\n") {
+        let mut lines = block.lines().skip_while(|line| line.trim().is_empty());
+
+        // The timing line is either the first line or follows a cue identifier
+        let Some(first) = lines.next() else {
+            continue;
+        };
+        let timing = if first.contains("-->") {
+            first
+        } else {
+            match lines.next() {
+                Some(line) if line.contains("-->") => line,
+                _ => continue, // header, NOTE, STYLE or REGION block
+            }
+        };
+
+        let Some((start, rest)) = timing.split_once("-->") else {
+            continue;
+        };
+        // Cue settings (`align:start position:0%`) follow the end time
+        let end = rest.split_whitespace().next().unwrap_or("");
+
+        let (Some(start), Some(end)) = (parse_timestamp(start), parse_timestamp(end)) else {
+            continue;
+        };
+
+        let text = normalize_text(&strip_markup(&lines.collect::<Vec<_>>().join("\n")));
+        if text.is_empty() || end <= start {
+            continue;
+        }
+
+        segments.push(Segment {
+            start,
+            end,
+            text,
+            words: None,
+        });
+    }
+
+    segments
+}
+
+fn xml_attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let pattern = format!("{}=\"", name);
+    let start = tag.find(&pattern)? + pattern.len();
+    let end = tag[start..].find('"')? + start;
+    Some(&tag[start..end])
+}
+
+/// Parses YouTube's SRV3 (`<timedtext format="3">`) captions into segments with absolute times.
+pub fn parse_srv3(content: &str) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut rest = content;
+
+    while let Some(open) = rest.find("<p ") {
+        rest = &rest[open..];
+        let Some(tag_end) = rest.find('>') else {
+            break;
+        };
+        let tag = &rest[..tag_end];
+
+        let (body, next) = if tag.ends_with('/') {
+            ("", &rest[tag_end + 1..])
+        } else {
+            match rest.find("</p>") {
+                Some(close) => (&rest[tag_end + 1..close], &rest[close + "</p>".len()..]),
+                None => break,
+            }
+        };
+        rest = next;
+
+        let start_ms = xml_attribute(tag, "t").and_then(|t| t.parse::<f64>().ok());
+        let duration_ms = xml_attribute(tag, "d").and_then(|d| d.parse::<f64>().ok());
+        let (Some(start_ms), Some(duration_ms)) = (start_ms, duration_ms) else {
+            continue;
+        };
+
+        let text = normalize_text(&strip_markup(body));
+        if text.is_empty() || duration_ms <= 0.0 {
+            continue;
+        }
+
+        segments.push(Segment {
+            start: start_ms / 1000.0,
+            end: (start_ms + duration_ms) / 1000.0,
+            text,
+            words: None,
+        });
+    }
+
+    segments
+}
+
+/// Keeps the segments overlapping the `start`-`end` clip (in seconds of the
+/// original video) and re-times them so the clip starts at zero.
+pub fn clip_segments(segments: Vec<Segment>, start: f64, end: f64) -> Vec<Segment> {
+    segments
+        .into_iter()
+        .filter(|segment| segment.end > start && segment.start < end)
+        .map(|segment| Segment {
+            start: segment.start.max(start) - start,
+            end: segment.end.min(end) - start,
+            text: segment.text,
+            words: segment.words.map(|words| clip_words(words, start, end)),
+        })
+        .collect()
+}
+
+fn clip_words(words: Vec<Word>, start: f64, end: f64) -> Vec<Word> {
+    words
+        .into_iter()
+        .filter(|word| word.end.is_none_or(|e| e > start) && word.start.is_none_or(|s| s < end))
+        .map(|word| Word {
+            start: word.start.map(|s| s.max(start) - start),
+            end: word.end.map(|e| e.min(end) - start),
+            ..word
+        })
+        .collect()
+}
+
+/// Formats seconds as `hh:mm:ss<separator>mmm`.
+fn format_timestamp(seconds: f64, separator: char) -> String {
+    let millis = (seconds.max(0.0) * 1000.0).round() as u64;
+
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        separator,
+        millis % 1000
+    )
+}
+
+fn escape_vtt(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Cue text with a `<hh:mm:ss.mmm>` timestamp tag before every timed word
+/// after the first, so players can highlight the words as they're spoken.
+fn vtt_word_text(words: &[Word], offset: f64) -> String {
+    let mut text = String::new();
+
+    for (index, word) in words.iter().enumerate() {
+        if index > 0 {
+            text.push(' ');
+            if let Some(start) = word.start {
+                text.push_str(&format!("<{}>", format_timestamp(start + offset, '.')));
+            }
+        }
+        text.push_str(&escape_vtt(word.word.trim()));
+    }
+
+    text
+}
+
+/// Renders `segments` in `format`, with every time shifted by `offset` seconds.
+pub fn render_segments(
+    segments: &[Segment],
+    format: SubtitleFormat,
+    offset: f64,
+    word_timing: bool,
+) -> String {
+    let mut output = String::new();
+
+    match format {
+        SubtitleFormat::Srt => {
+            for (index, segment) in segments.iter().enumerate() {
+                output.push_str(&format!(
+                    "{}\n{} --> {}\n{}\n\n",
+                    index + 1,
+                    format_timestamp(segment.start + offset, ','),
+                    format_timestamp(segment.end + offset, ','),
+                    segment.text.trim()
+                ));
+            }
+        }
+        SubtitleFormat::Vtt => {
+            output.push_str("WEBVTT\n\n");
+            for segment in segments {
+                let text = match &segment.words {
+                    Some(words) if word_timing && !words.is_empty() => vtt_word_text(words, offset),
+                    _ => escape_vtt(segment.text.trim()),
+                };
+                output.push_str(&format!(
+                    "{} --> {}\n{}\n\n",
+                    format_timestamp(segment.start + offset, '.'),
+                    format_timestamp(segment.end + offset, '.'),
+                    text
+                ));
+            }
+        }
+        SubtitleFormat::Tsv => {
+            // Same layout as whisper's `--output-tsv`: integer milliseconds
+            output.push_str("start\tend\ttext\n");
+            for segment in segments {
+                output.push_str(&format!(
+                    "{}\t{}\t{}\n",
+                    ((segment.start + offset) * 1000.0).round() as i64,
+                    ((segment.end + offset) * 1000.0).round() as i64,
+                    normalize_text(&segment.text)
+                ));
+            }
+        }
+        SubtitleFormat::Txt => {
+            for segment in segments {
+                output.push_str(segment.text.trim());
+                output.push('\n');
+            }
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn times(segments: &[Segment]) -> Vec<(f64, f64, &str)> {
+        segments
+            .iter()
+            .map(|s| (s.start, s.end, s.text.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn parses_youtube_vtt() {
+        let vtt = "WEBVTT
+Kind: captions
+Language: en
+
+NOTE written by a human
+
+00:00:01.120 --> 00:00:03.400 align:start position:0%
+We&#39;re no strangers
+to <i>love</i>
+
+intro
+00:01:02.000 --> 00:01:04.500
+<v Rick>You know the rules &amp; so do I</v>
+
+00:01:05.000 --> 00:01:05.000
+zero length cue is dropped
+";
+
+        assert_eq!(
+            times(&parse_vtt(vtt)),
+            vec![
+                (1.12, 3.4, "We're no strangers to love"),
+                (62.0, 64.5, "You know the rules & so do I"),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_srv3() {
+        let srv3 = r#"<?xml version="1.0" encoding="utf-8" ?><timedtext format="3">
+<body>
+<p t="1120" d="2280">We&#39;re no strangers</p>
+<p t="3400" d="1500"><s>to</s><s t="400"> love</s></p>
+<p t="5000" d="0">empty</p>
+<p t="6000" d="1000"/>
+</body>
+</timedtext>"#;
+
+        assert_eq!(
+            times(&parse_srv3(srv3)),
+            vec![(1.12, 3.4, "We're no strangers"), (3.4, 4.9, "to love")]
+        );
+    }
+
+    #[test]
+    fn clips_and_retimes_to_section() {
+        let segments = vec![
+            Segment {
+                start: 5.0,
+                end: 9.0,
+                text: "before".to_string(),
+                words: None,
+            },
+            Segment {
+                start: 9.0,
+                end: 12.0,
+                text: "straddles start".to_string(),
+                words: None,
+            },
+            Segment {
+                start: 12.0,
+                end: 15.0,
+                text: "inside".to_string(),
+                words: None,
+            },
+            Segment {
+                start: 19.0,
+                end: 22.0,
+                text: "straddles end".to_string(),
+                words: None,
+            },
+            Segment {
+                start: 20.0,
+                end: 25.0,
+                text: "after".to_string(),
+                words: None,
+            },
+        ];
+
+        assert_eq!(
+            times(&clip_segments(segments, 10.0, 20.0)),
+            vec![
+                (0.0, 2.0, "straddles start"),
+                (2.0, 5.0, "inside"),
+                (9.0, 10.0, "straddles end"),
+            ]
+        );
+    }
+
+    #[test]
+    fn clips_word_timings() {
+        let word = |word: &str, start: Option<f64>, end: Option<f64>| Word {
+            word: word.to_string(),
+            start,
+            end,
+            score: Some(0.9),
+        };
+        let segments = vec![Segment {
+            start: 8.0,
+            end: 12.0,
+            text: "cut off here".to_string(),
+            words: Some(vec![
+                word("cut", Some(8.0), Some(9.5)),
+                word("off", Some(9.5), Some(10.5)),
+                word("here", None, None),
+            ]),
+        }];
+
+        let clipped = clip_segments(segments, 10.0, 20.0);
+
+        assert_eq!(
+            clipped[0].words,
+            Some(vec![
+                word("off", Some(0.0), Some(0.5)),
+                word("here", None, None),
+            ])
+        );
+    }
+
+    fn export_segments() -> Vec<Segment> {
+        let word = |word: &str, start: f64, end: f64| Word {
+            word: word.to_string(),
+            start: Some(start),
+            end: Some(end),
+            score: None,
+        };
+
+        vec![
+            Segment {
+                start: 0.5,
+                end: 2.25,
+                text: "Fish & chips".to_string(),
+                words: Some(vec![
+                    word("Fish", 0.5, 0.9),
+                    word("&", 1.0, 1.1),
+                    word("chips", 1.2, 2.2),
+                ]),
+            },
+            Segment {
+                start: 3661.0,
+                end: 3662.0,
+                text: "an hour later".to_string(),
+                words: None,
+            },
+        ]
+    }
+
+    #[test]
+    fn renders_srt() {
+        assert_eq!(
+            render_segments(&export_segments(), SubtitleFormat::Srt, 0.0, false),
+            "1\n00:00:00,500 --> 00:00:02,250\nFish & chips\n\n\
+             2\n01:01:01,000 --> 01:01:02,000\nan hour later\n\n"
+        );
+    }
+
+    #[test]
+    fn renders_vtt_with_word_timing_and_offset() {
+        assert_eq!(
+            render_segments(&export_segments(), SubtitleFormat::Vtt, 10.0, true),
+            "WEBVTT\n\n\
+             00:00:10.500 --> 00:00:12.250\n\
+             Fish <00:00:11.000>&amp; <00:00:11.200>chips\n\n\
+             01:01:11.000 --> 01:01:12.000\nan hour later\n\n"
+        );
+
+        let plain = render_segments(&export_segments(), SubtitleFormat::Vtt, 0.0, false);
+        assert!(plain.contains("\nFish &amp; chips\n"));
+    }
+
+    #[test]
+    fn renders_tsv_and_text() {
+        assert_eq!(
+            render_segments(&export_segments(), SubtitleFormat::Tsv, 0.0, false),
+            "start\tend\ttext\n500\t2250\tFish & chips\n3661000\t3662000\tan hour later\n"
+        );
+        assert_eq!(
+            render_segments(&export_segments(), SubtitleFormat::Txt, 0.0, false),
+            "Fish & chips\nan hour later\n"
+        );
+    }
+
+    #[test]
+    fn exported_vtt_parses_back() {
+        let vtt = render_segments(&export_segments(), SubtitleFormat::Vtt, 0.0, true);
+
+        assert_eq!(
+            times(&parse_vtt(&vtt)),
+            vec![
+                (0.5, 2.25, "Fish & chips"),
+                (3661.0, 3662.0, "an hour later")
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_srt() {
+        let srt = "1
+00:00:01,000 --> 00:00:02,500
+{\\an8}Hello <i>there</i>
+
+2
+00:00:03,000 --> 00:00:04,000
+General
+Kenobi
+
+3
+00:00:05,000 --> broken
+
+4
+00:00:06,000 --> 00:00:07,000
+
+";
+
+        assert_eq!(
+            times(&parse_srt(srt)),
+            vec![(1.0, 2.5, "Hello there"), (3.0, 4.0, "General Kenobi")]
+        );
+    }
+
+    #[test]
+    fn parses_ass() {
+        let ass = r"[Script Info]
+Title: test
+
+[V4+ Styles]
+Format: Name, Fontname, Fontsize
+Style: Default,Arial,20
+
+[Events]
+Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+Dialogue: 0,0:00:01.12,0:00:03.40,Default,,0,0,0,,{\i1}Well,{\i0} hello\Nworld
+Comment: 0,0:00:02.00,0:00:03.00,Default,,0,0,0,,not shown
+Dialogue: 0,0:00:04.00,0:00:03.00,Default,,0,0,0,,ends before it starts
+Dialogue: 0,bad,0:00:05.00,Default,,0,0,0,,bad start
+Dialogue: 0,0:00:05.00,0:00:06.00,Default,,0,0,0,,{\pos(10,10)}
+";
+
+        assert_eq!(
+            times(&parse_ass(ass)),
+            vec![(1.12, 3.4, "Well, hello world")]
+        );
+    }
+
+    #[test]
+    fn resolves_overlapping_cues() {
+        let srt = "1
+00:00:05,000 --> 00:00:08,000
+third
+
+2
+00:00:01,000 --> 00:00:04,000
+first
+
+3
+00:00:01,000 --> 00:00:03,000
+premier
+
+4
+00:00:03,500 --> 00:00:06,000
+second
+";
+
+        assert_eq!(
+            times(&parse_subtitle_file("clip.srt", srt).unwrap()),
+            vec![
+                (1.0, 3.5, "first premier"),
+                (3.5, 5.0, "second"),
+                (5.0, 8.0, "third"),
+            ]
+        );
+    }
+
+    #[test]
+    fn detects_format_from_content() {
+        let vtt = "\u{feff}WEBVTT\n\n00:01.000 --> 00:02.000\nhi\n";
+
+        assert_eq!(
+            times(&parse_subtitle_file("subs.txt", vtt).unwrap()),
+            vec![(1.0, 2.0, "hi")]
+        );
+        assert!(parse_subtitle_file("notes.txt", "just text").is_err());
+    }
+
+    #[test]
+    fn aligns_imported_subtitles_to_clip() {
+        let cue = |start: f64, end: f64| Segment {
+            start,
+            end,
+            text: "cue".to_string(),
+            words: None,
+        };
+
+        // Made for the whole video: cut to 60-90 and re-timed
+        let whole = align_to_clip(
+            vec![cue(50.0, 61.0), cue(70.0, 75.0), cue(95.0, 99.0)],
+            60.0,
+            90.0,
+        );
+        assert_eq!(times(&whole), vec![(0.0, 1.0, "cue"), (10.0, 15.0, "cue")]);
+
+        // Made for the clip: kept as is
+        let clip = align_to_clip(vec![cue(0.0, 10.0), cue(25.0, 30.5)], 60.0, 90.0);
+        assert_eq!(times(&clip), vec![(0.0, 10.0, "cue"), (25.0, 30.0, "cue")]);
+    }
+}
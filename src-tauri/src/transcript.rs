@@ -0,0 +1,690 @@
+use std::io::ErrorKind;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::{
+    auth::require_audio,
+    config::get_data_path,
+    db::Db,
+    error::AppError,
+    query::transcript::{
+        edit_transcript, get_audios_without_transcript, get_transcript as get_transcript_segments,
+        get_transcript_revisions as get_revisions, replace_transcript,
+        undo_transcript_edit as undo_edit, TranscriptRevision, TranscriptSegment,
+    },
+    service::wx::{Segment, Word},
+    subtitle::{align_to_clip, parse_subtitle_file, render_segments, SubtitleFormat},
+    DbState,
+};
+
+#[derive(Debug, Default, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportTranscriptOptions {
+    /// Adds inline word timestamps to WebVTT cues
+    pub word_timing: bool,
+    /// Shifts the times by the clip's `startTime`, so they line up with the
+    /// original video instead of the clip
+    pub source_offset: bool,
+}
+
+/// Where `split_transcript_segment` cuts a segment.
+#[derive(Debug, Serialize, Deserialize, Clone, specta::Type)]
+#[serde(tag = "type")]
+pub enum SplitPoint {
+    /// Before the word at `index`, needs word timings
+    Word { index: usize },
+    /// At `at` seconds, the text is cut after `text_offset` characters
+    Time { at: f64, text_offset: usize },
+}
+
+/// Replaces the text, keeping the word timings when the words only changed
+/// in spelling (same number of words).
+pub(crate) fn retext_segment(segment: &Segment, text: &str) -> Result<Segment, String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err("Segment text can't be empty".to_string());
+    }
+
+    let new_words: Vec<&str> = text.split_whitespace().collect();
+    let words = segment
+        .words
+        .as_ref()
+        .filter(|words| words.len() == new_words.len())
+        .map(|words| {
+            words
+                .iter()
+                .zip(new_words)
+                .map(|(word, new_word)| Word {
+                    word: new_word.to_string(),
+                    ..word.clone()
+                })
+                .collect()
+        });
+
+    Ok(Segment {
+        text: text.to_string(),
+        words,
+        ..segment.clone()
+    })
+}
+
+pub(crate) fn split_segment(
+    segment: &Segment,
+    point: &SplitPoint,
+) -> Result<(Segment, Segment), String> {
+    let (at, first_text, second_text, words) = match point {
+        SplitPoint::Word { index } => {
+            let words = segment
+                .words
+                .as_ref()
+                .ok_or("The segment has no word timings".to_string())?;
+            if *index == 0 || *index >= words.len() {
+                return Err(format!("Can't split before word {}", index));
+            }
+
+            let at = words[*index]
+                .start
+                .or(words[index - 1].end)
+                .ok_or("The word has no timing".to_string())?;
+            let join = |words: &[Word]| {
+                words
+                    .iter()
+                    .map(|word| word.word.trim())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            };
+            let (first, second) = words.split_at(*index);
+
+            (
+                at,
+                join(first),
+                join(second),
+                Some((first.to_vec(), second.to_vec())),
+            )
+        }
+        SplitPoint::Time { at, text_offset } => {
+            let offset = segment
+                .text
+                .char_indices()
+                .nth(*text_offset)
+                .map(|(offset, _)| offset)
+                .ok_or("The split position is outside the text".to_string())?;
+            let (first, second) = segment.text.split_at(offset);
+
+            // Words starting at or after the split go to the second segment
+            let words = segment.words.as_ref().map(|words| {
+                let split = words
+                    .iter()
+                    .position(|word| word.start.is_some_and(|start| start >= *at))
+                    .unwrap_or(words.len());
+                (words[..split].to_vec(), words[split..].to_vec())
+            });
+
+            (
+                *at,
+                first.trim().to_string(),
+                second.trim().to_string(),
+                words,
+            )
+        }
+    };
+
+    if at <= segment.start || at >= segment.end {
+        return Err(format!(
+            "The split time {} is outside the segment ({} - {})",
+            at, segment.start, segment.end
+        ));
+    }
+    if first_text.is_empty() || second_text.is_empty() {
+        return Err("Both parts of a split segment need text".to_string());
+    }
+
+    let (first_words, second_words) = words.unzip();
+
+    Ok((
+        Segment {
+            start: segment.start,
+            end: at,
+            text: first_text,
+            words: first_words,
+        },
+        Segment {
+            start: at,
+            end: segment.end,
+            text: second_text,
+            words: second_words,
+        },
+    ))
+}
+
+pub(crate) fn merge_segments(first: &Segment, second: &Segment) -> Segment {
+    let words = match (&first.words, &second.words) {
+        (Some(first), Some(second)) => Some([first.as_slice(), second.as_slice()].concat()),
+        _ => None,
+    };
+
+    Segment {
+        start: first.start,
+        end: second.end,
+        text: format!("{} {}", first.text.trim(), second.text.trim()),
+        words,
+    }
+}
+
+/// `segment` moved to `start`-`end`, which has to fit in `min`-`max`: between
+/// its neighbours and within the clip.
+pub(crate) fn retime_segment(
+    segment: &Segment,
+    start: f64,
+    end: f64,
+    min: f64,
+    max: f64,
+) -> Result<Segment, String> {
+    if start < 0.0 || end <= start {
+        return Err(format!("Invalid segment time: {} - {}", start, end));
+    }
+    if start < min || end > max {
+        return Err(format!(
+            "Segment time {} - {} must stay within {} - {}",
+            start, end, min, max
+        ));
+    }
+
+    Ok(Segment {
+        start,
+        end,
+        ..segment.clone()
+    })
+}
+
+/// Loads `data/<audio_id>/subtitle.json` into `transcript_segment`. Returns the
+/// number of segments, or `None` if the audio has no subtitle file.
+pub(crate) async fn import_subtitle_file(
+    app_handle: &AppHandle,
+    db: &Db,
+    audio_id: &str,
+) -> Result<Option<usize>, AppError> {
+    let data_path = get_data_path(app_handle).unwrap_or("/data/".to_string());
+    let path = format!("{}/{}/subtitle.json", data_path, audio_id);
+
+    let contents = match tokio::fs::read_to_string(&path).await {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(AppError::Io(format!("Failed to read {}: {}", path, e))),
+    };
+
+    let segments: Vec<Segment> = serde_json::from_str(&contents)
+        .map_err(|e| AppError::Validation(format!("Failed to parse {}: {}", path, e)))?;
+
+    replace_transcript(db, audio_id, &segments).await?;
+
+    Ok(Some(segments.len()))
+}
+
+/// Moves the `subtitle.json` files written before transcripts were stored in
+/// the database. Audios that already have rows are skipped, so this only does
+/// work once per audio.
+pub async fn import_subtitle_files(app_handle: AppHandle) {
+    let state = app_handle.state::<DbState>();
+    let db = &state.db;
+
+    let audio_ids = match get_audios_without_transcript(db).await {
+        Ok(audio_ids) => audio_ids,
+        Err(e) => {
+            println!("❌ Failed to list audios to import: {}", e);
+            return;
+        }
+    };
+
+    for audio_id in audio_ids {
+        match import_subtitle_file(&app_handle, db, &audio_id).await {
+            Ok(Some(count)) => println!("📄 Imported {} segments of {}", count, audio_id),
+            Ok(None) => {}
+            Err(e) => println!("❌ Failed to import the subtitles of {}: {}", audio_id, e),
+        }
+    }
+}
+
+pub(crate) async fn load_transcript(
+    app_handle: &AppHandle,
+    db: &Db,
+    user_id: &str,
+    audio_id: &str,
+) -> Result<Vec<TranscriptSegment>, AppError> {
+    let segments = get_transcript_segments(db, user_id, audio_id).await?;
+
+    // Subtitles written to disk directly (e.g. downloaded captions) aren't imported yet
+    if segments.is_empty()
+        && import_subtitle_file(app_handle, db, audio_id)
+            .await?
+            .is_some()
+    {
+        return Ok(get_transcript_segments(db, user_id, audio_id).await?);
+    }
+
+    Ok(segments)
+}
+
+fn segment_at(segments: &[TranscriptSegment], index: usize) -> Result<Segment, AppError> {
+    segments
+        .get(index)
+        .cloned()
+        .map(Segment::from)
+        .ok_or(AppError::NotFound(format!(
+            "Segment {} does not exist",
+            index
+        )))
+}
+
+/// Stores an edit replacing `old_segments` at `index` and rewrites
+/// `subtitle.json`, which the player still reads, from the new transcript.
+#[allow(clippy::too_many_arguments)]
+async fn apply_edit(
+    app_handle: &AppHandle,
+    db: &Db,
+    user_id: &str,
+    audio_id: &str,
+    action: &str,
+    index: usize,
+    old_segments: &[Segment],
+    new_segments: &[Segment],
+) -> Result<Vec<TranscriptSegment>, AppError> {
+    edit_transcript(
+        db,
+        user_id,
+        audio_id,
+        action,
+        index as i64,
+        old_segments,
+        new_segments,
+    )
+    .await?;
+
+    write_subtitle_file(app_handle, db, user_id, audio_id).await
+}
+
+async fn write_subtitle_file(
+    app_handle: &AppHandle,
+    db: &Db,
+    user_id: &str,
+    audio_id: &str,
+) -> Result<Vec<TranscriptSegment>, AppError> {
+    let transcript = get_transcript_segments(db, user_id, audio_id).await?;
+
+    let segments: Vec<Segment> = transcript.iter().cloned().map(Segment::from).collect();
+    let json = serde_json::to_string_pretty(&segments).map_err(|e| AppError::Io(e.to_string()))?;
+
+    let data_path = get_data_path(app_handle).unwrap_or("/data/".to_string());
+    tokio::fs::write(format!("{}/{}/subtitle.json", data_path, audio_id), json).await?;
+
+    Ok(transcript)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_transcript(
+    app_handle: AppHandle,
+    state: tauri::State<'_, DbState>,
+    audio_id: String,
+) -> Result<Vec<TranscriptSegment>, AppError> {
+    let db = &state.db;
+
+    let (user, _) = require_audio(&app_handle, db, &audio_id).await?;
+    let user_id = user.user_id;
+
+    load_transcript(&app_handle, db, &user_id, &audio_id).await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn edit_transcript_segment(
+    app_handle: AppHandle,
+    state: tauri::State<'_, DbState>,
+    audio_id: String,
+    segment_index: usize,
+    text: String,
+) -> Result<Vec<TranscriptSegment>, AppError> {
+    let db = &state.db;
+
+    let (user, _) = require_audio(&app_handle, db, &audio_id).await?;
+    let user_id = user.user_id;
+
+    let transcript = load_transcript(&app_handle, db, &user_id, &audio_id).await?;
+    let segment = segment_at(&transcript, segment_index)?;
+    let edited = retext_segment(&segment, &text).map_err(AppError::Validation)?;
+
+    apply_edit(
+        &app_handle,
+        db,
+        &user_id,
+        &audio_id,
+        "edit_text",
+        segment_index,
+        &[segment],
+        &[edited],
+    )
+    .await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn split_transcript_segment(
+    app_handle: AppHandle,
+    state: tauri::State<'_, DbState>,
+    audio_id: String,
+    segment_index: usize,
+    point: SplitPoint,
+) -> Result<Vec<TranscriptSegment>, AppError> {
+    let db = &state.db;
+
+    let (user, _) = require_audio(&app_handle, db, &audio_id).await?;
+    let user_id = user.user_id;
+
+    let transcript = load_transcript(&app_handle, db, &user_id, &audio_id).await?;
+    let segment = segment_at(&transcript, segment_index)?;
+    let (first, second) = split_segment(&segment, &point).map_err(AppError::Validation)?;
+
+    apply_edit(
+        &app_handle,
+        db,
+        &user_id,
+        &audio_id,
+        "split",
+        segment_index,
+        &[segment],
+        &[first, second],
+    )
+    .await
+}
+
+/// Merges the segment at `segment_index` with the one after it.
+#[tauri::command]
+#[specta::specta]
+pub async fn merge_transcript_segments(
+    app_handle: AppHandle,
+    state: tauri::State<'_, DbState>,
+    audio_id: String,
+    segment_index: usize,
+) -> Result<Vec<TranscriptSegment>, AppError> {
+    let db = &state.db;
+
+    let (user, _) = require_audio(&app_handle, db, &audio_id).await?;
+    let user_id = user.user_id;
+
+    let transcript = load_transcript(&app_handle, db, &user_id, &audio_id).await?;
+    let first = segment_at(&transcript, segment_index)?;
+    let second = segment_at(&transcript, segment_index + 1)?;
+    let merged = merge_segments(&first, &second);
+
+    apply_edit(
+        &app_handle,
+        db,
+        &user_id,
+        &audio_id,
+        "merge",
+        segment_index,
+        &[first, second],
+        &[merged],
+    )
+    .await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn retime_transcript_segment(
+    app_handle: AppHandle,
+    state: tauri::State<'_, DbState>,
+    audio_id: String,
+    segment_index: usize,
+    start: f64,
+    end: f64,
+) -> Result<Vec<TranscriptSegment>, AppError> {
+    let db = &state.db;
+
+    let (user, audio) = require_audio(&app_handle, db, &audio_id).await?;
+    let user_id = user.user_id;
+
+    let transcript = load_transcript(&app_handle, db, &user_id, &audio_id).await?;
+    let segment = segment_at(&transcript, segment_index)?;
+
+    // Segment times are relative to the clip, and segments don't overlap
+    let min = match segment_index {
+        0 => 0.0,
+        index => transcript[index - 1].end_time,
+    };
+    let max = transcript
+        .get(segment_index + 1)
+        .map_or(f64::MAX, |next| next.start_time)
+        .min((audio.end_time - audio.start_time).into());
+    let retimed = retime_segment(&segment, start, end, min, max).map_err(AppError::Validation)?;
+
+    apply_edit(
+        &app_handle,
+        db,
+        &user_id,
+        &audio_id,
+        "retime",
+        segment_index,
+        &[segment],
+        &[retimed],
+    )
+    .await
+}
+
+/// Reverts the latest transcript edit that wasn't undone yet.
+#[tauri::command]
+#[specta::specta]
+pub async fn undo_transcript_edit(
+    app_handle: AppHandle,
+    state: tauri::State<'_, DbState>,
+    audio_id: String,
+) -> Result<Vec<TranscriptSegment>, AppError> {
+    let db = &state.db;
+
+    let (user, _) = require_audio(&app_handle, db, &audio_id).await?;
+    let user_id = user.user_id;
+
+    undo_edit(db, &audio_id)
+        .await?
+        .ok_or(AppError::Validation("Nothing to undo".to_string()))?;
+
+    write_subtitle_file(&app_handle, db, &user_id, &audio_id).await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_transcript_revisions(
+    app_handle: AppHandle,
+    state: tauri::State<'_, DbState>,
+    audio_id: String,
+) -> Result<Vec<TranscriptRevision>, AppError> {
+    let db = &state.db;
+
+    let (user, _) = require_audio(&app_handle, db, &audio_id).await?;
+    let user_id = user.user_id;
+
+    Ok(get_revisions(db, &user_id, &audio_id).await?)
+}
+
+/// Replaces the transcript of `audio_id` with an SRT, WebVTT or ASS file.
+#[tauri::command]
+#[specta::specta]
+pub async fn import_subtitles(
+    app_handle: AppHandle,
+    state: tauri::State<'_, DbState>,
+    audio_id: String,
+    path: String,
+) -> Result<Vec<TranscriptSegment>, AppError> {
+    let db = &state.db;
+
+    let (user, audio) = require_audio(&app_handle, db, &audio_id).await?;
+
+    let content = tokio::fs::read_to_string(&path)
+        .await
+        .map_err(|e| AppError::Io(format!("Failed to read {}: {}", path, e)))?;
+
+    let segments = align_to_clip(
+        parse_subtitle_file(&path, &content).map_err(AppError::Validation)?,
+        f64::from(audio.start_time),
+        f64::from(audio.end_time),
+    );
+    if segments.is_empty() {
+        return Err(AppError::Validation(format!(
+            "No subtitles for this clip found in {}",
+            path
+        )));
+    }
+
+    replace_transcript(db, &audio_id, &segments).await?;
+
+    write_subtitle_file(&app_handle, db, &user.user_id, &audio_id).await
+}
+
+/// Writes the transcript of `audio_id` to `path` in `format`.
+#[tauri::command]
+#[specta::specta]
+pub async fn export_transcript(
+    app_handle: AppHandle,
+    state: tauri::State<'_, DbState>,
+    audio_id: String,
+    format: SubtitleFormat,
+    path: String,
+    options: Option<ExportTranscriptOptions>,
+) -> Result<(), AppError> {
+    let db = &state.db;
+    let options = options.unwrap_or_default();
+
+    let (user, audio) = require_audio(&app_handle, db, &audio_id).await?;
+
+    let transcript = load_transcript(&app_handle, db, &user.user_id, &audio_id).await?;
+    if transcript.is_empty() {
+        return Err(AppError::NotFound(
+            "The audio has no transcript to export".to_string(),
+        ));
+    }
+
+    let segments: Vec<Segment> = transcript.into_iter().map(Segment::from).collect();
+    let offset = if options.source_offset {
+        f64::from(audio.start_time)
+    } else {
+        0.0
+    };
+
+    tokio::fs::write(
+        &path,
+        render_segments(&segments, format, offset, options.word_timing),
+    )
+    .await
+    .map_err(|e| AppError::Io(format!("Failed to write {}: {}", path, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn word(word: &str, start: f64, end: f64) -> Word {
+        Word {
+            word: word.to_string(),
+            start: Some(start),
+            end: Some(end),
+            score: Some(0.9),
+        }
+    }
+
+    fn segment() -> Segment {
+        Segment {
+            start: 1.0,
+            end: 4.0,
+            text: "the quick fox".to_string(),
+            words: Some(vec![
+                word("the", 1.0, 1.4),
+                word("quick", 1.5, 2.4),
+                word("fox", 2.6, 3.9),
+            ]),
+        }
+    }
+
+    #[test]
+    fn splits_at_word() {
+        let (first, second) = split_segment(&segment(), &SplitPoint::Word { index: 2 }).unwrap();
+
+        assert_eq!(
+            (first.start, first.end, first.text.as_str()),
+            (1.0, 2.6, "the quick")
+        );
+        assert_eq!(
+            (second.start, second.end, second.text.as_str()),
+            (2.6, 4.0, "fox")
+        );
+        assert_eq!(first.words.unwrap().len(), 2);
+        assert_eq!(second.words, Some(vec![word("fox", 2.6, 3.9)]));
+    }
+
+    #[test]
+    fn splits_at_time() {
+        let point = SplitPoint::Time {
+            at: 1.45,
+            text_offset: 3,
+        };
+        let (first, second) = split_segment(&segment(), &point).unwrap();
+
+        assert_eq!(first.text, "the");
+        assert_eq!(second.text, "quick fox");
+        assert_eq!(first.words, Some(vec![word("the", 1.0, 1.4)]));
+    }
+
+    #[test]
+    fn rejects_invalid_splits() {
+        let outside = SplitPoint::Time {
+            at: 5.0,
+            text_offset: 3,
+        };
+        assert!(split_segment(&segment(), &outside).is_err());
+        assert!(split_segment(&segment(), &SplitPoint::Word { index: 0 }).is_err());
+
+        let no_text = SplitPoint::Time {
+            at: 2.0,
+            text_offset: 0,
+        };
+        assert!(split_segment(&segment(), &no_text).is_err());
+    }
+
+    #[test]
+    fn split_and_merge_round_trip() {
+        let original = segment();
+        let (first, second) = split_segment(&original, &SplitPoint::Word { index: 1 }).unwrap();
+        let merged = merge_segments(&first, &second);
+
+        assert_eq!(
+            (merged.start, merged.end, merged.text.as_str()),
+            (original.start, original.end, original.text.as_str())
+        );
+        assert_eq!(merged.words, original.words);
+    }
+
+    #[test]
+    fn retext_keeps_timings_of_respelled_words() {
+        let respelled = retext_segment(&segment(), "the quick fax").unwrap();
+        assert_eq!(respelled.words.unwrap()[2], word("fax", 2.6, 3.9));
+
+        let rewritten = retext_segment(&segment(), "a quick brown fox").unwrap();
+        assert_eq!(rewritten.words, None);
+
+        assert!(retext_segment(&segment(), "  ").is_err());
+    }
+
+    #[test]
+    fn retime_stays_between_neighbours() {
+        let retimed = retime_segment(&segment(), 0.5, 4.5, 0.5, 5.0).unwrap();
+        assert_eq!((retimed.start, retimed.end), (0.5, 4.5));
+        assert_eq!(retimed.text, "the quick fox");
+
+        assert!(retime_segment(&segment(), 3.0, 2.0, 0.0, 5.0).is_err());
+        // Overlapping the previous or the next segment
+        assert!(retime_segment(&segment(), 0.9, 4.0, 1.0, 5.0).is_err());
+        assert!(retime_segment(&segment(), 1.0, 5.1, 0.0, 5.0).is_err());
+        // Outside the clip
+        assert!(retime_segment(&segment(), -1.0, 4.0, 0.0, 5.0).is_err());
+    }
+}
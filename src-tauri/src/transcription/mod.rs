@@ -0,0 +1,127 @@
+use std::fmt;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::{query::setting::AppSettings, service::wx::TranscriptionResponse};
+
+pub mod sidecar;
+pub mod whisperx;
+
+pub const DEFAULT_SERVICE_URL: &str = "http://localhost:8081";
+
+/// Lets the model detect the spoken language.
+pub const AUTO_LANGUAGE: &str = "auto";
+
+/// Normalizes a language code (`en`, `en-US`, `yue`) to the base ISO 639 code
+/// the models use. `auto` becomes `None`.
+pub fn parse_language(language: &str) -> Result<Option<String>, String> {
+    let language = language.trim().to_lowercase();
+    if language == AUTO_LANGUAGE {
+        return Ok(None);
+    }
+
+    let code = language.split(['-', '_']).next().unwrap_or_default();
+    let valid = (2..=3).contains(&code.len()) && code.chars().all(|c| c.is_ascii_lowercase());
+
+    if valid {
+        Ok(Some(code.to_string()))
+    } else {
+        Err(format!("Invalid language: {}", language))
+    }
+}
+
+/// Which backend `transcribe` uses. Stored lowercase in `app_settings.transcriptionBackend`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "lowercase")]
+pub enum TranscriptionBackendKind {
+    Sidecar,
+    Service,
+}
+
+impl TranscriptionBackendKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TranscriptionBackendKind::Sidecar => "sidecar",
+            TranscriptionBackendKind::Service => "service",
+        }
+    }
+}
+
+impl fmt::Display for TranscriptionBackendKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl TryFrom<String> for TranscriptionBackendKind {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "sidecar" => Ok(TranscriptionBackendKind::Sidecar),
+            "service" => Ok(TranscriptionBackendKind::Service),
+            _ => Err(format!("Unknown transcription backend: {}", value)),
+        }
+    }
+}
+
+pub struct TranscriptionRequest {
+    pub audio_path: String,
+    /// Output path without extension, the backends write `<output_path>.json`
+    pub output_path: String,
+    pub model: String,
+    pub model_path: String,
+    /// `None` to auto-detect
+    pub language: Option<String>,
+    pub initial_prompt: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TranscriptionUpdate {
+    pub status: String,
+    pub message: String,
+    pub progress: Option<f64>,
+}
+
+#[async_trait]
+pub trait TranscriptionBackend: Send + Sync {
+    /// Transcribes `request.audio_path`, reporting intermediate states through
+    /// `on_progress`. Completion and failure are only reported by the result.
+    async fn transcribe(
+        &self,
+        app_handle: &AppHandle,
+        request: &TranscriptionRequest,
+        on_progress: &(dyn Fn(TranscriptionUpdate) + Send + Sync),
+    ) -> Result<TranscriptionResponse, String>;
+}
+
+pub fn backend_for(settings: &AppSettings) -> Box<dyn TranscriptionBackend> {
+    match settings.transcription_backend {
+        TranscriptionBackendKind::Sidecar => Box::new(sidecar::WhipSidecar),
+        TranscriptionBackendKind::Service => Box::new(whisperx::WhisperXService::new(
+            settings
+                .model_proxy
+                .as_deref()
+                .unwrap_or(DEFAULT_SERVICE_URL),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_language_codes() {
+        assert_eq!(parse_language("auto"), Ok(None));
+        assert_eq!(parse_language(" AUTO "), Ok(None));
+        assert_eq!(parse_language("en"), Ok(Some("en".to_string())));
+        assert_eq!(parse_language("en-US"), Ok(Some("en".to_string())));
+        assert_eq!(parse_language("zh_TW"), Ok(Some("zh".to_string())));
+        assert_eq!(parse_language("yue"), Ok(Some("yue".to_string())));
+        assert!(parse_language("english").is_err());
+        assert!(parse_language("").is_err());
+    }
+}
@@ -0,0 +1,134 @@
+use async_trait::async_trait;
+use tauri::AppHandle;
+use tauri_plugin_shell::{
+    process::{CommandChild, CommandEvent},
+    ShellExt,
+};
+
+use crate::service::wx::{Segment, TranscriptionResponse};
+
+use super::{TranscriptionBackend, TranscriptionRequest, TranscriptionUpdate, AUTO_LANGUAGE};
+
+/// The bundled `whip_v2` binary, running the model locally.
+pub struct WhipSidecar;
+
+/// Kills whip_v2 when the transcription future is dropped (the job was cancelled).
+struct KillOnDrop(Option<CommandChild>);
+
+impl Drop for KillOnDrop {
+    fn drop(&mut self) {
+        if let Some(child) = self.0.take() {
+            let _ = child.kill();
+        }
+    }
+}
+
+/// Picks the `42%` style progress out of a line of whip_v2 output, as a 0-1 fraction.
+fn parse_progress(line: &str) -> Option<f64> {
+    line.split_whitespace()
+        .filter_map(|word| word.strip_suffix('%'))
+        .filter_map(|value| value.parse::<f64>().ok())
+        .next_back()
+        .map(|percent| (percent / 100.0).clamp(0.0, 1.0))
+}
+
+#[async_trait]
+impl TranscriptionBackend for WhipSidecar {
+    async fn transcribe(
+        &self,
+        app_handle: &AppHandle,
+        request: &TranscriptionRequest,
+        on_progress: &(dyn Fn(TranscriptionUpdate) + Send + Sync),
+    ) -> Result<TranscriptionResponse, String> {
+        let mut args = vec![
+            "--file",
+            &request.audio_path,
+            "--model",
+            &request.model,
+            "--model_path",
+            &request.model_path,
+            "--lang",
+            request.language.as_deref().unwrap_or(AUTO_LANGUAGE),
+            "--output",
+            &request.output_path,
+        ];
+        if let Some(initial_prompt) = &request.initial_prompt {
+            args.extend(["--initial_prompt", initial_prompt]);
+        }
+
+        let (mut rx, child) = app_handle
+            .shell()
+            .sidecar("whip_v2")
+            .map_err(|e| format!("can't find whip_v2 sidecar: {}", e))?
+            .args(args)
+            .spawn()
+            .map_err(|e| e.to_string())?;
+        let mut child = KillOnDrop(Some(child));
+
+        on_progress(TranscriptionUpdate {
+            status: "starting".to_string(),
+            message: format!("Loading model {}", request.model),
+            progress: None,
+        });
+
+        let mut last_line = String::new();
+        let mut result = Ok(());
+
+        while let Some(event) = rx.recv().await {
+            match event {
+                CommandEvent::Stdout(bytes) | CommandEvent::Stderr(bytes) => {
+                    let text = String::from_utf8_lossy(&bytes);
+                    for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+                        last_line = line.to_string();
+                        on_progress(TranscriptionUpdate {
+                            status: "transcribing".to_string(),
+                            message: line.to_string(),
+                            progress: parse_progress(line),
+                        });
+                    }
+                }
+                CommandEvent::Error(error) => last_line = error,
+                CommandEvent::Terminated(payload) if payload.code != Some(0) => {
+                    result = Err(format!(
+                        "whip_v2 exited with code {:?}: {}",
+                        payload.code, last_line
+                    ));
+                }
+                _ => {}
+            }
+        }
+
+        // Already exited
+        child.0 = None;
+        result?;
+
+        let output_file = format!("{}.json", request.output_path);
+        let contents = tokio::fs::read_to_string(&output_file)
+            .await
+            .map_err(|e| format!("Failed to read {}: {}", output_file, e))?;
+        let segments: Vec<Segment> = serde_json::from_str(&contents)
+            .map_err(|e| format!("Failed to parse {}: {}", output_file, e))?;
+
+        Ok(TranscriptionResponse {
+            segments,
+            output_file,
+            // whip_v2 doesn't report what it detected
+            language: request
+                .language
+                .clone()
+                .unwrap_or(AUTO_LANGUAGE.to_string()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_percent_progress() {
+        assert_eq!(parse_progress("progress = 45%"), Some(0.45));
+        assert_eq!(parse_progress("whisper_full: 100%"), Some(1.0));
+        assert_eq!(parse_progress("[00:00.000 --> 00:02.000] Hello"), None);
+    }
+}
@@ -0,0 +1,59 @@
+use async_trait::async_trait;
+use tauri::AppHandle;
+
+use crate::service::wx::{TranscriptionResponse, WhisperXClient};
+
+use super::{TranscriptionBackend, TranscriptionRequest, TranscriptionUpdate};
+
+/// The WhisperX HTTP service, streaming its status updates.
+pub struct WhisperXService {
+    client: WhisperXClient,
+}
+
+impl WhisperXService {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            client: WhisperXClient::new(base_url),
+        }
+    }
+}
+
+#[async_trait]
+impl TranscriptionBackend for WhisperXService {
+    async fn transcribe(
+        &self,
+        _app_handle: &AppHandle,
+        request: &TranscriptionRequest,
+        on_progress: &(dyn Fn(TranscriptionUpdate) + Send + Sync),
+    ) -> Result<TranscriptionResponse, String> {
+        match self.client.health_check().await {
+            Ok(true) => {}
+            Ok(false) => return Err("Service is not healthy".to_string()),
+            Err(e) => return Err(format!("Failed to check health: {}", e)),
+        }
+
+        self.client
+            .transcribe_streaming(
+                &request.audio_path,
+                Some(&request.model),
+                request.language.as_deref(),
+                Some(&request.model_path),
+                Some(&request.output_path),
+                request.initial_prompt.as_deref(),
+                |status_update| {
+                    // The final states are reported through the result
+                    if status_update.status == "complete" || status_update.status == "error" {
+                        return;
+                    }
+
+                    on_progress(TranscriptionUpdate {
+                        status: status_update.status,
+                        message: status_update.message,
+                        progress: status_update.progress,
+                    });
+                },
+            )
+            .await
+            .map_err(|e| e.to_string())
+    }
+}
@@ -1,74 +1,248 @@
-use serde::Serialize;
+use std::{collections::HashMap, sync::Mutex};
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
 use tauri::{AppHandle, Emitter, Manager};
-use tauri_plugin_shell::{process::CommandEvent, ShellExt};
+use tauri_plugin_shell::{
+    process::{CommandChild, CommandEvent},
+    ShellExt,
+};
 use uuid::Uuid;
 
-use crate::config::get_data_path;
+use crate::{
+    auth::{require_audio, require_user},
+    config::get_data_path,
+    error::AppError,
+    provider::{media_key, provider_for_url, MediaProvider},
+    query::{
+        audio::{find_audio_by_content_hash, find_overlapping_audios, AudioListItem},
+        commands::{audio_content_hash, remove_dir_all_safe},
+        download_queue::cancel_queued_download,
+        transcript::replace_transcript,
+    },
+    subtitle::{clip_segments, parse_srv3, parse_vtt},
+    DbState,
+};
+
+// Prefix for the lines printed by our `--progress-template`, so they can be told
+// apart from the rest of yt-dlp's output.
+const PROGRESS_PREFIX: &str = "[attune-progress]";
+
+#[derive(Debug, Serialize, Clone, PartialEq, specta::Type)]
+pub(crate) enum DownloadPhase {
+    Download,
+    PostProcess,
+}
 
-#[derive(Serialize, Clone, specta::Type)]
+#[derive(Debug, Serialize, Clone, PartialEq, specta::Type)]
 #[serde(tag = "type")] // This makes the variant name appear as "type"
-enum DownloadStatus {
-    Started,
-    Progress { message: String },
+pub(crate) enum DownloadStatus {
+    Started {
+        job_id: String,
+    },
+    Progress {
+        percent: Option<f64>,
+        downloaded_bytes: Option<u64>,
+        total_bytes: Option<u64>,
+        speed: Option<f64>,
+        eta_secs: Option<u64>,
+        phase: DownloadPhase,
+    },
     AlreadyDownloaded,
     Finished,
-    Error { message: String },
+    Cancelled {
+        job_id: String,
+    },
+    Error {
+        message: String,
+    },
 }
 
-// TODO: provide a abort button to frontned
-// TODO: return data format
-// TODO: add filename args (we could write into db first before download it)
+#[derive(Debug, Serialize, Clone, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadJobInfo {
+    pub job_id: String,
+    pub url: String,
+    pub start: i32,
+    pub end: i32,
+    pub started_at: i64,
+}
 
-#[tauri::command]
-#[specta::specta]
-pub async fn download_yt_sections(
-    app_handle: AppHandle,
-    url: String,
+pub(crate) struct DownloadJob {
+    info: DownloadJobInfo,
+    child: CommandChild,
+}
+
+/// Running yt-dlp downloads, keyed by the job id (the `data/<uuid>/` directory name).
+#[derive(Default)]
+pub struct DownloadState {
+    jobs: Mutex<HashMap<String, DownloadJob>>,
+}
+
+impl DownloadState {
+    pub(crate) fn insert(&self, info: DownloadJobInfo, child: CommandChild) {
+        let mut jobs = self.jobs.lock().unwrap();
+        jobs.insert(info.job_id.clone(), DownloadJob { info, child });
+    }
+
+    pub(crate) fn remove(&self, job_id: &str) -> Option<DownloadJob> {
+        self.jobs.lock().unwrap().remove(job_id)
+    }
+
+    fn list(&self) -> Vec<DownloadJobInfo> {
+        let jobs = self.jobs.lock().unwrap();
+        let mut list: Vec<DownloadJobInfo> = jobs.values().map(|job| job.info.clone()).collect();
+        list.sort_by_key(|info| info.started_at);
+        list
+    }
+}
+
+fn progress_templates() -> [String; 4] {
+    [
+        "--progress-template".to_string(),
+        format!(
+            "download:{} download %(progress.status)s %(progress.downloaded_bytes)s %(progress.total_bytes)s %(progress.total_bytes_estimate)s %(progress.speed)s %(progress.eta)s",
+            PROGRESS_PREFIX
+        ),
+        "--progress-template".to_string(),
+        format!(
+            "postprocess:{} postprocess %(progress.status)s %(progress.postprocessor)s",
+            PROGRESS_PREFIX
+        ),
+    ]
+}
+
+// yt-dlp prints `NA` for fields it doesn't know yet
+fn parse_field<T: std::str::FromStr>(field: &str) -> Option<T> {
+    if field == "NA" {
+        return None;
+    }
+    field.parse().ok()
+}
+
+/// Parses one line printed by the templates from `progress_templates`.
+/// Returns `None` for any other yt-dlp output.
+fn parse_progress_line(line: &str) -> Option<DownloadStatus> {
+    let fields: Vec<&str> = line
+        .trim()
+        .strip_prefix(PROGRESS_PREFIX)?
+        .split_whitespace()
+        .collect();
+
+    match fields.as_slice() {
+        ["download", status, downloaded, total, estimate, speed, eta] => {
+            let downloaded_bytes = parse_field::<f64>(downloaded).map(|v| v as u64);
+            let total_bytes = parse_field::<f64>(total)
+                .or_else(|| parse_field::<f64>(estimate))
+                .map(|v| v as u64);
+
+            let percent = match (*status, downloaded_bytes, total_bytes) {
+                ("finished", _, _) => Some(100.0),
+                (_, Some(done), Some(total)) if total > 0 => {
+                    Some((done as f64 / total as f64 * 100.0).min(100.0))
+                }
+                _ => None,
+            };
+
+            Some(DownloadStatus::Progress {
+                percent,
+                downloaded_bytes,
+                total_bytes,
+                speed: parse_field(speed),
+                eta_secs: parse_field::<f64>(eta).map(|v| v as u64),
+                phase: DownloadPhase::Download,
+            })
+        }
+        ["postprocess", status, ..] => Some(DownloadStatus::Progress {
+            percent: (*status == "finished").then_some(100.0),
+            downloaded_bytes: None,
+            total_bytes: None,
+            speed: None,
+            eta_secs: None,
+            phase: DownloadPhase::PostProcess,
+        }),
+        _ => None,
+    }
+}
+
+/// How a `run_download` call that didn't fail came to an end.
+pub(crate) enum DownloadOutcome {
+    Finished,
+    Cancelled,
+}
+
+/// Downloads the `start`-`end` section of `url` in yt-dlp `format` into
+/// `data/<job_id>/audio.m4a`, registering the yt-dlp process in `DownloadState`
+/// so it can be cancelled.
+pub(crate) async fn run_download(
+    app_handle: &AppHandle,
+    job_id: &str,
+    url: &str,
+    format: &str,
     start: i32,
     end: i32,
-) -> Result<String, String> {
+) -> Result<DownloadOutcome, String> {
     let already_download_error = format!("has already been downloaded");
 
-    let uuid = Uuid::new_v4().to_string();
-    let uuid_copy = uuid.clone();
-
-    let data_path = get_data_path(&app_handle).unwrap_or(format!("/data/"));
+    let data_path = get_data_path(app_handle).unwrap_or(format!("/data/"));
 
     app_handle
-        .emit("download_status", DownloadStatus::Started)
+        .emit(
+            "download_status",
+            DownloadStatus::Started {
+                job_id: job_id.to_string(),
+            },
+        )
         .map_err(|e| e.to_string())?;
 
     let yt_command = app_handle
         .shell()
         .sidecar("yt-dlp")
-        .expect("can't find yt-dlp sidecar");
-
-    let yt_command_copy = app_handle.clone();
+        .map_err(|e| format!("can't find yt-dlp sidecar: {}", e))?;
 
     let ffmpeg_path = app_handle
         .path()
         .resolve("ffmpeg", tauri::path::BaseDirectory::Resource)
-        .expect("failed to resolve ffmpeg path");
+        .map_err(|e| format!("failed to resolve ffmpeg path: {}", e))?;
+
+    let (mut rx, child) = yt_command
+        .args([
+            "--download-sections",
+            &format!("*{}-{}", start, end),
+            "-f",
+            format,
+            "-k",
+            "--extract-audio",
+            "--audio-format",
+            "m4a",
+            "--ffmpeg-location",
+            &ffmpeg_path.to_string_lossy(),
+            "--newline",
+            "-o",
+            &format!("{}/{}/audio.%(ext)s", data_path, job_id),
+            url,
+        ])
+        .args(progress_templates())
+        .spawn()
+        .map_err(|e| e.to_string())?;
+
+    let download_state = app_handle.state::<DownloadState>();
+    download_state.insert(
+        DownloadJobInfo {
+            job_id: job_id.to_string(),
+            url: url.to_string(),
+            start,
+            end,
+            started_at: Utc::now().timestamp(),
+        },
+        child,
+    );
 
+    let event_handle = app_handle.clone();
     let handle = tauri::async_runtime::spawn(async move {
-        let (mut rx, _child) = yt_command
-            .args([
-                "--download-sections",
-                &format!("*{}-{}", start, end),
-                "-f",
-                "mp4",
-                "-k",
-                "--extract-audio",
-                "--audio-format",
-                "m4a",
-                "--ffmpeg-location",
-                &ffmpeg_path.to_string_lossy(),
-                "-o",
-                &format!("{}/{}/audio.%(ext)s", data_path, uuid),
-                &url,
-            ])
-            .spawn()
-            .map_err(|e| e.to_string())?;
+        let app_handle = event_handle;
+        // yt-dlp reports the reason of a failure on stderr before exiting
+        let mut last_error: Option<String> = None;
 
         while let Some(event) = rx.recv().await {
             match event {
@@ -81,35 +255,34 @@ pub async fn download_yt_sections(
                             return Err("Video has already been downloaded".to_string());
                         }
 
-                        app_handle
-                            .emit(
-                                "download_status",
-                                DownloadStatus::Progress { message: text },
-                            )
-                            .map_err(|e| e.to_string())?;
+                        for progress in text.lines().filter_map(parse_progress_line) {
+                            app_handle
+                                .emit("download_status", progress)
+                                .map_err(|e| e.to_string())?;
+                        }
                     } else {
-                        dbg!("Parsing failed");
+                        println!("❌ Failed to parse yt-dlp output as UTF-8");
                     }
                 }
                 CommandEvent::Stderr(error_bytes) => {
                     if let Ok(error_text) = String::from_utf8(error_bytes) {
                         if error_text.contains("ERROR:") {
-                            app_handle.emit(
-                                "download_status",
-                                DownloadStatus::Error {
-                                    message: error_text,
-                                },
-                            )
+                            last_error = Some(error_text.trim().to_string());
+                            app_handle
+                                .emit(
+                                    "download_status",
+                                    DownloadStatus::Error {
+                                        message: error_text,
+                                    },
+                                )
+                                .map_err(|e| e.to_string())?;
                         } else {
-                            // Treat as progress information
-                            app_handle.emit(
-                                "download_status",
-                                DownloadStatus::Progress {
-                                    message: error_text,
-                                },
-                            )
+                            for progress in error_text.lines().filter_map(parse_progress_line) {
+                                app_handle
+                                    .emit("download_status", progress)
+                                    .map_err(|e| e.to_string())?;
+                            }
                         }
-                        .map_err(|e| e.to_string())?;
                     }
                 }
                 CommandEvent::Error(error) => {
@@ -126,7 +299,10 @@ pub async fn download_yt_sections(
                 CommandEvent::Terminated(payload) => {
                     // Handle process termination
                     if payload.code != Some(0) {
-                        return Err(format!("Process terminated with code: {:?}", payload.code));
+                        return Err(last_error.unwrap_or(format!(
+                            "Process terminated with code: {:?}",
+                            payload.code
+                        )));
                     }
                 }
                 _ => {}
@@ -135,11 +311,603 @@ pub async fn download_yt_sections(
         Ok(())
     });
 
-    let _ = handle.await.expect("Task failed");
+    let result = handle.await.map_err(|e| e.to_string())?;
+
+    // `cancel_download` takes the job out of the registry before killing it,
+    // so a missing entry means the download was cancelled and already cleaned up.
+    if download_state.remove(job_id).is_none() {
+        return Ok(DownloadOutcome::Cancelled);
+    }
+
+    result?;
 
-    yt_command_copy
+    app_handle
         .emit("download_status", DownloadStatus::Finished)
         .map_err(|e| e.to_string())?;
 
-    Ok(uuid_copy)
+    Ok(DownloadOutcome::Finished)
+}
+
+#[derive(Debug, Serialize, Clone, specta::Type)]
+#[serde(tag = "type")]
+pub enum SectionDownload {
+    Downloaded {
+        audio_id: String,
+    },
+    /// The user already has this clip, `audio` can be reused instead
+    Duplicate {
+        audio: AudioListItem,
+    },
+}
+
+// TODO: add filename args (we could write into db first before download it)
+
+/// Downloads a section of `url`. Unless `force` is set, an existing audio of the
+/// same video with an overlapping range (or, after downloading, with the same
+/// content) is returned as `Duplicate` instead.
+#[tauri::command]
+#[specta::specta]
+pub async fn download_yt_sections(
+    app_handle: AppHandle,
+    state: tauri::State<'_, DbState>,
+    url: String,
+    start: i32,
+    end: i32,
+    force: bool,
+) -> Result<SectionDownload, AppError> {
+    let db = &state.db;
+
+    let user = require_user(&app_handle, db).await?;
+
+    let provider = provider_for_url(&url).map_err(AppError::Validation)?;
+
+    check_section(&app_handle, provider.as_ref(), &url, start, end).await?;
+
+    if !force {
+        let key = media_key(&url);
+        let existing = find_overlapping_audios(db, &user.user_id, start, end)
+            .await?
+            .into_iter()
+            .find(|audio| media_key(&audio.url) == key);
+
+        if let Some(audio) = existing {
+            return Ok(SectionDownload::Duplicate { audio });
+        }
+    }
+
+    let uuid = Uuid::new_v4().to_string();
+
+    match provider
+        .download_section(&app_handle, &uuid, &url, start, end)
+        .await?
+    {
+        DownloadOutcome::Finished => {}
+        DownloadOutcome::Cancelled => {
+            return Err(AppError::Backend("Download cancelled".to_string()))
+        }
+    }
+
+    if !force {
+        // Same clip behind a different URL
+        if let Some(hash) = audio_content_hash(&app_handle, &uuid).await {
+            let existing = find_audio_by_content_hash(db, &user.user_id, &hash).await?;
+
+            if let Some(audio) = existing {
+                let data_path = get_data_path(&app_handle).unwrap_or("/data/".to_string());
+                let _ = remove_dir_all_safe(&format!("{}/{}", data_path, uuid)).await;
+                return Ok(SectionDownload::Duplicate { audio });
+            }
+        }
+    }
+
+    Ok(SectionDownload::Downloaded { audio_id: uuid })
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn cancel_download(
+    app_handle: AppHandle,
+    state: tauri::State<'_, DbState>,
+    download_state: tauri::State<'_, DownloadState>,
+    job_id: String,
+) -> Result<(), AppError> {
+    let job = download_state.remove(&job_id);
+
+    // Downloads started through the queue also have a row to cancel
+    let was_queued = cancel_queued_download(&state.db, &job_id).await?;
+
+    if job.is_none() && !was_queued {
+        return Err(AppError::NotFound(format!(
+            "No running download with id: {}",
+            job_id
+        )));
+    }
+
+    if let Some(job) = job {
+        job.child
+            .kill()
+            .map_err(|e| AppError::Backend(e.to_string()))?;
+    }
+
+    let data_path = get_data_path(&app_handle).unwrap_or("/data/".to_string());
+    remove_dir_all_safe(&format!("{}/{}", data_path, job_id)).await?;
+
+    app_handle.emit("download_status", DownloadStatus::Cancelled { job_id })?;
+
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn list_downloads(download_state: tauri::State<'_, DownloadState>) -> Vec<DownloadJobInfo> {
+    download_state.list()
+}
+
+#[derive(Debug, Deserialize)]
+struct YtDlpChapter {
+    title: Option<String>,
+    start_time: f64,
+    end_time: f64,
+}
+
+// The subset of `yt-dlp --dump-json` we care about
+#[derive(Debug, Deserialize)]
+struct YtDlpInfo {
+    id: String,
+    title: String,
+    description: Option<String>,
+    uploader: Option<String>,
+    duration: Option<f64>,
+    thumbnail: Option<String>,
+    chapters: Option<Vec<YtDlpChapter>>,
+    #[serde(default)]
+    subtitles: HashMap<String, serde_json::Value>,
+    #[serde(default)]
+    automatic_captions: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct VideoChapter {
+    pub title: String,
+    pub start_time: f64,
+    pub end_time: f64,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct VideoInfo {
+    pub id: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub uploader: Option<String>,
+    pub duration: Option<f64>,
+    pub thumbnail: Option<String>,
+    pub chapters: Vec<VideoChapter>,
+    pub subtitle_languages: Vec<String>,
+    pub automatic_caption_languages: Vec<String>,
+}
+
+fn caption_languages(captions: HashMap<String, serde_json::Value>) -> Vec<String> {
+    let mut languages: Vec<String> = captions
+        .into_keys()
+        // yt-dlp lists the live chat replay as a subtitle track
+        .filter(|language| language != "live_chat")
+        .collect();
+    languages.sort();
+    languages
+}
+
+fn parse_video_info(json: &str) -> Result<VideoInfo, String> {
+    let info: YtDlpInfo =
+        serde_json::from_str(json).map_err(|e| format!("Invalid yt-dlp output: {}", e))?;
+
+    let chapters = info
+        .chapters
+        .unwrap_or_default()
+        .into_iter()
+        .enumerate()
+        .map(|(i, chapter)| VideoChapter {
+            title: chapter
+                .title
+                .unwrap_or_else(|| format!("Chapter {}", i + 1)),
+            start_time: chapter.start_time,
+            end_time: chapter.end_time,
+        })
+        .collect();
+
+    Ok(VideoInfo {
+        id: info.id,
+        title: info.title,
+        description: info.description,
+        uploader: info.uploader,
+        duration: info.duration,
+        thumbnail: info.thumbnail,
+        chapters,
+        subtitle_languages: caption_languages(info.subtitles),
+        automatic_caption_languages: caption_languages(info.automatic_captions),
+    })
+}
+
+/// Checks that the `start`-`end` section (in seconds) fits in a video of `duration` seconds.
+fn validate_section(duration: Option<f64>, start: i32, end: i32) -> Result<(), String> {
+    if start < 0 {
+        return Err(format!("Section start must not be negative: {}", start));
+    }
+    if end <= start {
+        return Err(format!(
+            "Section end ({}) must be after its start ({})",
+            end, start
+        ));
+    }
+    if let Some(duration) = duration {
+        if f64::from(end) > duration.ceil() {
+            return Err(format!(
+                "Section end ({}) is past the end of the video ({}s)",
+                end, duration
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Checks a section against the video before it is downloaded or queued.
+pub(crate) async fn check_section(
+    app_handle: &AppHandle,
+    provider: &dyn MediaProvider,
+    url: &str,
+    start: i32,
+    end: i32,
+) -> Result<(), AppError> {
+    // Bounds that are wrong for any video fail without a lookup
+    validate_section(None, start, end).map_err(AppError::Validation)?;
+
+    let info = provider.resolve(app_handle, url).await?;
+
+    validate_section(info.duration, start, end).map_err(AppError::Validation)
+}
+
+/// Runs `yt-dlp --dump-json` for a single video and parses its metadata.
+pub(crate) async fn dump_video_info(
+    app_handle: &AppHandle,
+    url: &str,
+) -> Result<VideoInfo, String> {
+    let output = app_handle
+        .shell()
+        .sidecar("yt-dlp")
+        .map_err(|e| format!("can't find yt-dlp sidecar: {}", e))?
+        .args(["--dump-json", "--skip-download", "--no-playlist", url])
+        .output()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(stderr
+            .lines()
+            .find(|line| line.contains("ERROR:"))
+            .unwrap_or("Failed to fetch video info")
+            .to_string());
+    }
+
+    parse_video_info(&String::from_utf8_lossy(&output.stdout))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn fetch_video_info(
+    app_handle: AppHandle,
+    url: String,
+    start: Option<i32>,
+    end: Option<i32>,
+) -> Result<VideoInfo, AppError> {
+    let info = provider_for_url(&url)
+        .map_err(AppError::Validation)?
+        .resolve(&app_handle, &url)
+        .await?;
+
+    if let (Some(start), Some(end)) = (start, end) {
+        validate_section(info.duration, start, end).map_err(AppError::Validation)?;
+    }
+
+    Ok(info)
+}
+
+/// Finds the `captions.<lang>.<ext>` file yt-dlp wrote into `dir`.
+async fn find_caption_file(dir: &str) -> Option<std::path::PathBuf> {
+    let mut entries = tokio::fs::read_dir(dir).await.ok()?;
+
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name.starts_with("captions.") && (name.ends_with(".vtt") || name.ends_with(".srv3")) {
+            return Some(entry.path());
+        }
+    }
+
+    None
+}
+
+/// Downloads the video's manual (human-made) captions of the audio and writes
+/// the part covering its clip to `data/<audio_id>/subtitle.json`, in the same
+/// segment format the WhisperX service produces, and stores it as the transcript.
+#[tauri::command]
+#[specta::specta]
+pub async fn download_yt_captions(
+    app_handle: AppHandle,
+    state: tauri::State<'_, DbState>,
+    audio_id: String,
+    language: String,
+) -> Result<usize, AppError> {
+    let db = &state.db;
+
+    // Only the owner's audios, and only ids that name a real `data/` directory
+    let (_, audio) = require_audio(&app_handle, db, &audio_id).await?;
+
+    let data_path = get_data_path(&app_handle).unwrap_or("/data/".to_string());
+    let audio_dir = format!("{}/{}", data_path, audio_id);
+
+    let output = app_handle
+        .shell()
+        .sidecar("yt-dlp")
+        .map_err(|e| AppError::Backend(format!("can't find yt-dlp sidecar: {}", e)))?
+        .args([
+            "--skip-download",
+            "--no-playlist",
+            "--write-subs",
+            "--no-write-auto-subs",
+            "--sub-langs",
+            &language,
+            "--sub-format",
+            "vtt/srv3",
+            "-o",
+            &format!("{}/captions.%(ext)s", audio_dir),
+            &audio.url,
+        ])
+        .output()
+        .await
+        .map_err(|e| AppError::Backend(e.to_string()))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(AppError::Backend(
+            stderr
+                .lines()
+                .find(|line| line.contains("ERROR:"))
+                .unwrap_or("Failed to download captions")
+                .to_string(),
+        ));
+    }
+
+    let caption_file = find_caption_file(&audio_dir)
+        .await
+        .ok_or(AppError::NotFound(format!(
+            "No captions available for language: {}",
+            language
+        )))?;
+
+    let content = tokio::fs::read_to_string(&caption_file).await?;
+    let _ = tokio::fs::remove_file(&caption_file).await;
+
+    let cues = if caption_file.extension().is_some_and(|ext| ext == "srv3") {
+        parse_srv3(&content)
+    } else {
+        parse_vtt(&content)
+    };
+    let segments = clip_segments(cues, audio.start_time.into(), audio.end_time.into());
+
+    if segments.is_empty() {
+        return Err(AppError::NotFound(
+            "The captions don't cover the downloaded section".to_string(),
+        ));
+    }
+
+    let json = serde_json::to_string_pretty(&segments).map_err(|e| AppError::Io(e.to_string()))?;
+    tokio::fs::write(format!("{}/subtitle.json", audio_dir), json).await?;
+    replace_transcript(db, &audio_id, &segments).await?;
+
+    Ok(segments.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn progress(
+        percent: Option<f64>,
+        downloaded_bytes: Option<u64>,
+        total_bytes: Option<u64>,
+        speed: Option<f64>,
+        eta_secs: Option<u64>,
+        phase: DownloadPhase,
+    ) -> DownloadStatus {
+        DownloadStatus::Progress {
+            percent,
+            downloaded_bytes,
+            total_bytes,
+            speed,
+            eta_secs,
+            phase,
+        }
+    }
+
+    #[test]
+    fn parses_download_progress_with_known_total() {
+        let line = "[attune-progress] download downloading 1048576 4194304 NA 524288.25 6\n";
+
+        assert_eq!(
+            parse_progress_line(line),
+            Some(progress(
+                Some(25.0),
+                Some(1048576),
+                Some(4194304),
+                Some(524288.25),
+                Some(6),
+                DownloadPhase::Download,
+            ))
+        );
+    }
+
+    #[test]
+    fn falls_back_to_total_bytes_estimate() {
+        // ffmpeg section downloads only report an estimated size
+        let line = "[attune-progress] download downloading 262144 NA 1048576.0 131072.0 NA";
+
+        assert_eq!(
+            parse_progress_line(line),
+            Some(progress(
+                Some(25.0),
+                Some(262144),
+                Some(1048576),
+                Some(131072.0),
+                None,
+                DownloadPhase::Download,
+            ))
+        );
+    }
+
+    #[test]
+    fn unknown_sizes_have_no_percent() {
+        let line = "[attune-progress] download downloading NA NA NA NA NA";
+
+        assert_eq!(
+            parse_progress_line(line),
+            Some(progress(
+                None,
+                None,
+                None,
+                None,
+                None,
+                DownloadPhase::Download
+            ))
+        );
+    }
+
+    #[test]
+    fn finished_download_is_complete() {
+        let line = "[attune-progress] download finished 3145728 NA NA NA NA";
+
+        assert_eq!(
+            parse_progress_line(line),
+            Some(progress(
+                Some(100.0),
+                Some(3145728),
+                None,
+                None,
+                None,
+                DownloadPhase::Download,
+            ))
+        );
+    }
+
+    #[test]
+    fn parses_post_processing() {
+        let started = "[attune-progress] postprocess started FFmpegExtractAudio";
+        let finished = "[attune-progress] postprocess finished FFmpegExtractAudio";
+
+        assert_eq!(
+            parse_progress_line(started),
+            Some(progress(
+                None,
+                None,
+                None,
+                None,
+                None,
+                DownloadPhase::PostProcess
+            ))
+        );
+        assert_eq!(
+            parse_progress_line(finished),
+            Some(progress(
+                Some(100.0),
+                None,
+                None,
+                None,
+                None,
+                DownloadPhase::PostProcess,
+            ))
+        );
+    }
+
+    #[test]
+    fn ignores_other_output() {
+        let lines = [
+            "[youtube] Extracting URL: https://www.youtube.com/watch?v=dQw4w9WgXcQ",
+            "[info] dQw4w9WgXcQ: Downloading 1 format(s): 18",
+            "[download]  12.5% of   3.00MiB at  512.00KiB/s ETA 00:05",
+            "[ExtractAudio] Destination: audio.m4a",
+            "[attune-progress] download downloading 1 2",
+            "",
+        ];
+
+        for line in lines {
+            assert_eq!(parse_progress_line(line), None, "{}", line);
+        }
+    }
+
+    #[test]
+    fn parses_video_info() {
+        // Trimmed `yt-dlp --dump-json --skip-download` output
+        let json = r#"{
+            "id": "dQw4w9WgXcQ",
+            "title": "Rick Astley - Never Gonna Give You Up (Official Music Video)",
+            "description": "The official video for “Never Gonna Give You Up”",
+            "uploader": "Rick Astley",
+            "duration": 212,
+            "thumbnail": "https://i.ytimg.com/vi/dQw4w9WgXcQ/maxresdefault.jpg",
+            "chapters": [
+                {"start_time": 0.0, "title": "Intro", "end_time": 18.0},
+                {"start_time": 18.0, "end_time": 212.0}
+            ],
+            "subtitles": {
+                "en": [{"ext": "vtt", "url": "https://example.com/en.vtt"}],
+                "de-DE": [{"ext": "vtt", "url": "https://example.com/de.vtt"}],
+                "live_chat": [{"ext": "json", "url": "https://example.com/chat"}]
+            },
+            "automatic_captions": {
+                "ja": [{"ext": "srv3", "url": "https://example.com/ja.srv3"}]
+            },
+            "formats": [{"format_id": "18", "ext": "mp4"}]
+        }"#;
+
+        let info = parse_video_info(json).unwrap();
+
+        assert_eq!(info.id, "dQw4w9WgXcQ");
+        assert_eq!(info.uploader.as_deref(), Some("Rick Astley"));
+        assert_eq!(info.duration, Some(212.0));
+        assert_eq!(
+            info.chapters,
+            vec![
+                VideoChapter {
+                    title: "Intro".to_string(),
+                    start_time: 0.0,
+                    end_time: 18.0,
+                },
+                VideoChapter {
+                    title: "Chapter 2".to_string(),
+                    start_time: 18.0,
+                    end_time: 212.0,
+                },
+            ]
+        );
+        assert_eq!(info.subtitle_languages, vec!["de-DE", "en"]);
+        assert_eq!(info.automatic_caption_languages, vec!["ja"]);
+    }
+
+    #[test]
+    fn parses_video_info_without_optional_fields() {
+        let info = parse_video_info(r#"{"id": "abc", "title": "Live stream"}"#).unwrap();
+
+        assert_eq!(info.duration, None);
+        assert!(info.chapters.is_empty());
+        assert!(info.subtitle_languages.is_empty());
+    }
+
+    #[test]
+    fn validates_section_against_duration() {
+        assert!(validate_section(Some(212.4), 10, 213).is_ok());
+        assert!(validate_section(None, 10, 5000).is_ok());
+        assert!(validate_section(Some(212.0), 10, 213).is_err());
+        assert!(validate_section(Some(212.0), -1, 20).is_err());
+        assert!(validate_section(Some(212.0), 20, 20).is_err());
+    }
 }
@@ -1,12 +1,12 @@
 import { commands } from "./tauri";
 import type { AudioListItem } from "./tauri";
 
-export async function getAudioList(token: string): Promise<AudioListItem[]> {
+export async function getAudioList(): Promise<AudioListItem[]> {
     try {
-        const result = await commands.handleGetAudioList(token);
+        const result = await commands.handleGetAudioList();
 
         if (result.status === "error") {
-            throw new Error(result.error);
+            throw new Error(result.error.message);
         }
 
         return result.data;
@@ -6,7 +6,7 @@ export const audioListKey = Symbol("audioList");
 
 export type AudioListContext = {
     audioList: AudioListItem[];
-    refreshAudioList: (token: string) => Promise<void>;
+    refreshAudioList: () => Promise<void>;
     addAudioItem: (item: AudioListItem) => void;
     removeAudioItem: (id: string) => void;
 };
@@ -14,9 +14,9 @@ export type AudioListContext = {
 export function createAudioListContext(): AudioListContext {
     let audioList: AudioListItem[] = $state.raw([]);
 
-    const refreshAudioList = async (token: string) => {
+    const refreshAudioList = async () => {
         try {
-            audioList = await getAudioList(token);
+            audioList = await getAudioList();
             console.log("load");
         } catch (error) {
             console.error("Failed to refresh audio list:", error);
@@ -13,4 +13,6 @@ export type DownloadSectionParam = {
     start: number;
     end: number;
     url: string;
+    // Download even if the user already has this clip
+    force?: boolean;
 };
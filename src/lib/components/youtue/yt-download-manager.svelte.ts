@@ -1,4 +1,5 @@
 import { commands } from "$lib/tauri";
+import type { SectionDownload } from "$lib/tauri";
 import { listen } from "@tauri-apps/api/event";
 import type { UnlistenFn } from "@tauri-apps/api/event";
 
@@ -56,11 +57,17 @@ export class YtDownloadManager {
         start,
         end,
         url,
-    }: DownloadSectionParam): Promise<string> {
-        const result = await commands.downloadYtSections(url, start, end);
+        force = false,
+    }: DownloadSectionParam): Promise<SectionDownload> {
+        const result = await commands.downloadYtSections(
+            url,
+            start,
+            end,
+            force,
+        );
 
         if (result.status === "error") {
-            throw new Error(result.error);
+            throw new Error(result.error.message);
         }
 
         return result.data;
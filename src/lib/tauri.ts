@@ -8,71 +8,274 @@ export const commands = {
 async greet(name: string) : Promise<string> {
     return await TAURI_INVOKE("greet", { name });
 },
-async downloadYtSections(url: string, start: number, end: number) : Promise<Result<string, string>> {
+/**
+ * Downloads a section of `url`. Unless `force` is set, an existing audio of the
+ * same video with an overlapping range (or, after downloading, with the same
+ * content) is returned as `Duplicate` instead.
+ */
+async downloadYtSections(url: string, start: number, end: number, force: boolean) : Promise<Result<SectionDownload, AppError>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("download_yt_sections", { url, start, end }) };
+    return { status: "ok", data: await TAURI_INVOKE("download_yt_sections", { url, start, end, force }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async startTranscribe(audioId: string, model: string) : Promise<Result<null, string>> {
+async cancelDownload(jobId: string) : Promise<Result<null, AppError>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("start_transcribe", { audioId, model }) };
+    return { status: "ok", data: await TAURI_INVOKE("cancel_download", { jobId }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async startTranscribeService(audioId: string, model: string) : Promise<Result<null, string>> {
+async listDownloads() : Promise<DownloadJobInfo[]> {
+    return await TAURI_INVOKE("list_downloads");
+},
+async fetchVideoInfo(url: string, start: number | null, end: number | null) : Promise<Result<VideoInfo, AppError>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("start_transcribe_service", { audioId, model }) };
+    return { status: "ok", data: await TAURI_INVOKE("fetch_video_info", { url, start, end }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async startTranscribeServiceStreaming(token: string, audioId: string, model: string, initialPrompt: string) : Promise<Result<null, string>> {
+/**
+ * Downloads the video's manual (human-made) captions of the audio and writes
+ * the part covering its clip to `data/<audio_id>/subtitle.json`, in the same
+ * segment format the WhisperX service produces.
+ */
+async downloadYtCaptions(audioId: string, language: string) : Promise<Result<number, AppError>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("start_transcribe_service_streaming", { token, audioId, model, initialPrompt }) };
+    return { status: "ok", data: await TAURI_INVOKE("download_yt_captions", { audioId, language }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async checkModelHealth() : Promise<Result<boolean, string>> {
+async importLocalMedia(path: string, title: string) : Promise<Result<AudioListItem, AppError>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("check_model_health") };
+    return { status: "ok", data: await TAURI_INVOKE("import_local_media", { path, title }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async startOauthServer(state: string) : Promise<Result<number, string>> {
+async enqueueDownload(request: EnqueueDownloadRequest) : Promise<Result<DownloadQueueItem, AppError>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("start_oauth_server", { state }) };
+    return { status: "ok", data: await TAURI_INVOKE("enqueue_download", { request }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async stopOauthServer(port: number) : Promise<Result<string, string>> {
+async getDownloadQueue() : Promise<Result<DownloadQueueItem[], AppError>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("stop_oauth_server", { port }) };
+    return { status: "ok", data: await TAURI_INVOKE("get_download_queue") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Queues a transcription of `data/<audio_id>/audio.m4a` into `subtitle.json`
+ * with the backend chosen in the settings and returns right away. Both
+ * backends emit the same `transcription-progress`, `transcription-complete`
+ * and `transcription-error` events, the job state is kept in `transcription_job`.
+ */
+async transcribe(audioId: string, options: TranscribeOptions) : Promise<Result<TranscriptionJob, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("transcribe", { audioId, options }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * The latest transcription job of the audio, `None` if it was never transcribed.
+ */
+async getTranscriptionStatus(audioId: string) : Promise<Result<TranscriptionJob | null, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_transcription_status", { audioId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async cancelTranscription(audioId: string) : Promise<Result<null, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("cancel_transcription", { audioId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getTranscript(audioId: string) : Promise<Result<TranscriptSegment[], AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_transcript", { audioId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async editTranscriptSegment(audioId: string, segmentIndex: number, text: string) : Promise<Result<TranscriptSegment[], AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("edit_transcript_segment", { audioId, segmentIndex, text }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async splitTranscriptSegment(audioId: string, segmentIndex: number, point: SplitPoint) : Promise<Result<TranscriptSegment[], AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("split_transcript_segment", { audioId, segmentIndex, point }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Merges the segment at `segment_index` with the one after it.
+ */
+async mergeTranscriptSegments(audioId: string, segmentIndex: number) : Promise<Result<TranscriptSegment[], AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("merge_transcript_segments", { audioId, segmentIndex }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async retimeTranscriptSegment(audioId: string, segmentIndex: number, start: number, end: number) : Promise<Result<TranscriptSegment[], AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("retime_transcript_segment", { audioId, segmentIndex, start, end }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Reverts the latest transcript edit that wasn't undone yet.
+ */
+async undoTranscriptEdit(audioId: string) : Promise<Result<TranscriptSegment[], AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("undo_transcript_edit", { audioId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getTranscriptRevisions(audioId: string) : Promise<Result<TranscriptRevision[], AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_transcript_revisions", { audioId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Writes the transcript of `audio_id` to `path` in `format`.
+ */
+async exportTranscript(audioId: string, format: SubtitleFormat, path: string, options: ExportTranscriptOptions | null) : Promise<Result<null, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("export_transcript", { audioId, format, path, options }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async handleLogin(sub: string, email: string, name: string, picture: string | null, emailVerified: boolean, tokens: TokenData) : Promise<Result<string, string>> {
+/**
+ * Replaces the transcript of `audio_id` with an SRT, WebVTT or ASS file.
+ */
+async importSubtitles(audioId: string, path: string) : Promise<Result<TranscriptSegment[], AppError>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("handle_login", { sub, email, name, picture, emailVerified, tokens }) };
+    return { status: "ok", data: await TAURI_INVOKE("import_subtitles", { audioId, path }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async checkPersistUser() : Promise<Result<SessionWithUser | null, string>> {
+/**
+ * Scores `typed_text` against transcript segment `segment_id` (the segment
+ * index, like `dictationId`) and stores the attempt.
+ */
+async submitDictationAttempt(audioId: string, segmentId: number, typedText: string) : Promise<Result<DictationAttempt, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("submit_dictation_attempt", { audioId, segmentId, typedText }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Attempt history of the audio, newest first, optionally for one segment.
+ */
+async getDictationAttempts(audioId: string, segmentId: number | null) : Promise<Result<DictationAttempt[], AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_dictation_attempts", { audioId, segmentId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Bookmarked segments due for review across all audios of the user.
+ */
+async getDueReviews(limit: number | null) : Promise<Result<ReviewCard[], AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_due_reviews", { limit }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Grades a review from 0 (forgot) to 5 (perfect recall) and reschedules the card.
+ */
+async gradeReview(cardId: number, grade: number) : Promise<Result<ReviewCard, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("grade_review", { cardId, grade }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getPracticeStats(range: StatsRange) : Promise<Result<PracticeStats, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_practice_stats", { range }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async checkModelHealth() : Promise<Result<boolean, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("check_model_health") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Starts a Google sign in with PKCE. The result comes as an
+ * `oauth_code_state` event once the browser is redirected back.
+ */
+async startOauthServer() : Promise<Result<OAuthStart, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("start_oauth_server") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async stopOauthServer(port: number) : Promise<Result<string, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("stop_oauth_server", { port }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async checkPersistUser() : Promise<Result<CurrentUser | null, AppError>> {
     try {
     return { status: "ok", data: await TAURI_INVOKE("check_persist_user") };
 } catch (e) {
@@ -80,7 +283,7 @@ async checkPersistUser() : Promise<Result<SessionWithUser | null, string>> {
     else return { status: "error", error: e  as any };
 }
 },
-async logoutUser() : Promise<Result<null, string>> {
+async logoutUser() : Promise<Result<null, AppError>> {
     try {
     return { status: "ok", data: await TAURI_INVOKE("logout_user") };
 } catch (e) {
@@ -88,7 +291,40 @@ async logoutUser() : Promise<Result<null, string>> {
     else return { status: "error", error: e  as any };
 }
 },
-async handleCreateAudio(audioData: CreateAudioData) : Promise<Result<null, string>> {
+/**
+ * Signs the user out of every session, on this and other devices.
+ */
+async logoutAllSessions() : Promise<Result<null, AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("logout_all_sessions") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * The live sessions of the signed in user, on this and other devices.
+ */
+async listSessions() : Promise<Result<SessionInfo[], AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("list_sessions") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Signs a session out, revoking the current one signs this app out too.
+ */
+async revokeSession(sessionId: string) : Promise<Result<SessionInfo[], AppError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("revoke_session", { sessionId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async handleCreateAudio(audioData: CreateAudioData) : Promise<Result<null, AppError>> {
     try {
     return { status: "ok", data: await TAURI_INVOKE("handle_create_audio", { audioData }) };
 } catch (e) {
@@ -96,97 +332,97 @@ async handleCreateAudio(audioData: CreateAudioData) : Promise<Result<null, strin
     else return { status: "error", error: e  as any };
 }
 },
-async handleGetAudioList(token: string) : Promise<Result<AudioListItem[], string>> {
+async handleGetAudioList() : Promise<Result<AudioListItem[], AppError>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("handle_get_audio_list", { token }) };
+    return { status: "ok", data: await TAURI_INVOKE("handle_get_audio_list") };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async handleGetAudioItem(token: string, audioId: string) : Promise<Result<AudioListItem, string>> {
+async handleGetAudioItem(audioId: string) : Promise<Result<AudioListItem, AppError>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("handle_get_audio_item", { token, audioId }) };
+    return { status: "ok", data: await TAURI_INVOKE("handle_get_audio_item", { audioId }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async handleUpdateAudioTranscribe(token: string, audioId: string) : Promise<Result<AudioListItem, string>> {
+async handleUpdateAudioTranscribe(audioId: string) : Promise<Result<AudioListItem, AppError>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("handle_update_audio_transcribe", { token, audioId }) };
+    return { status: "ok", data: await TAURI_INVOKE("handle_update_audio_transcribe", { audioId }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async handleDeleteAudio(token: string, audioId: string) : Promise<Result<AudioListItem[], string>> {
+async handleDeleteAudio(audioId: string) : Promise<Result<AudioListItem[], AppError>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("handle_delete_audio", { token, audioId }) };
+    return { status: "ok", data: await TAURI_INVOKE("handle_delete_audio", { audioId }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async handleCreateBookmarkItem(token: string, audioId: string, bookmarkId: number) : Promise<Result<BookmarkDictationView[], string>> {
+async handleCreateBookmarkItem(audioId: string, bookmarkId: number) : Promise<Result<BookmarkDictationView[], AppError>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("handle_create_bookmark_item", { token, audioId, bookmarkId }) };
+    return { status: "ok", data: await TAURI_INVOKE("handle_create_bookmark_item", { audioId, bookmarkId }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async handleDeleteBookmarkItem(token: string, audioId: string, bookmarkId: number) : Promise<Result<BookmarkDictationView[], string>> {
+async handleDeleteBookmarkItem(audioId: string, bookmarkId: number) : Promise<Result<BookmarkDictationView[], AppError>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("handle_delete_bookmark_item", { token, audioId, bookmarkId }) };
+    return { status: "ok", data: await TAURI_INVOKE("handle_delete_bookmark_item", { audioId, bookmarkId }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async handleCreateDictationItem(token: string, audioId: string, dictationId: number) : Promise<Result<BookmarkDictationView[], string>> {
+async handleCreateDictationItem(audioId: string, dictationId: number) : Promise<Result<BookmarkDictationView[], AppError>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("handle_create_dictation_item", { token, audioId, dictationId }) };
+    return { status: "ok", data: await TAURI_INVOKE("handle_create_dictation_item", { audioId, dictationId }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async handleDeleteDictationItem(token: string, audioId: string, dictationId: number) : Promise<Result<BookmarkDictationView[], string>> {
+async handleDeleteDictationItem(audioId: string, dictationId: number) : Promise<Result<BookmarkDictationView[], AppError>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("handle_delete_dictation_item", { token, audioId, dictationId }) };
+    return { status: "ok", data: await TAURI_INVOKE("handle_delete_dictation_item", { audioId, dictationId }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async handleGetBookmarkDictationCombined(token: string, audioId: string) : Promise<Result<BookmarkDictationView[], string>> {
+async handleGetBookmarkDictationCombined(audioId: string) : Promise<Result<BookmarkDictationView[], AppError>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("handle_get_bookmark_dictation_combined", { token, audioId }) };
+    return { status: "ok", data: await TAURI_INVOKE("handle_get_bookmark_dictation_combined", { audioId }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async handleGetAppSettings(token: string) : Promise<Result<AppSettings, string>> {
+async handleGetAppSettings() : Promise<Result<AppSettings, AppError>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("handle_get_app_settings", { token }) };
+    return { status: "ok", data: await TAURI_INVOKE("handle_get_app_settings") };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async handleUpdateAppSettings(token: string, request: UpdateSettingsRequest) : Promise<Result<AppSettings, string>> {
+async handleUpdateAppSettings(request: UpdateSettingsRequest) : Promise<Result<AppSettings, AppError>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("handle_update_app_settings", { token, request }) };
+    return { status: "ok", data: await TAURI_INVOKE("handle_update_app_settings", { request }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async handleUpdateUserName(token: string, newName: string) : Promise<Result<null, string>> {
+async handleUpdateUserName(newName: string) : Promise<Result<null, AppError>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("handle_update_user_name", { token, newName }) };
+    return { status: "ok", data: await TAURI_INVOKE("handle_update_user_name", { newName }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
@@ -204,13 +440,176 @@ async handleUpdateUserName(token: string, newName: string) : Promise<Result<null
 
 /** user-defined types **/
 
-export type AppSettings = { id: number; currentUserId: string | null; theme: string; language: string; selectedModel: string; modelProxy: string | null; lastLogin: string | null; autoLogin: boolean }
-export type AudioListItem = { id: string; title: string; description: string | null; url: string; thumbnail: string | null; startTime: number; endTime: number; provider: string; tag: string | null; transcribe: number; initialPrompt: string | null; updatedAt: string }
-export type BookmarkDictationView = { userId: string; audioId: string; bookmarkId: number | null; bookmarkPosition: number | null; bookmarkCreatedAt: string | null; dictationId: number | null; dictationPosition: number | null; dictationCreatedAt: string | null }
-export type CreateAudioData = { audio_id: string; token: string; title: string; description: string | null; url: string; thumbnail: string; start_time: number; end_time: number; provider: string; tag: string | null }
-export type SessionWithUser = { userId: string; accessToken: string; name: string; email: string; picture: string | null }
-export type TokenData = { access_token: string | null; access_token_expires_at: number | null; refresh_token: string | null; refresh_token_expires_at: number | null }
-export type UpdateSettingsRequest = { theme: string | null; language: string | null; selectedModel: string | null; modelProxy: string | null; autoLogin: boolean | null }
+/**
+ * The error of every command, serialized as `{ "kind": "notFound", "message": "..." }`
+ * so the frontend can tell a signed out user from a missing item or a failed download.
+ */
+export type AppError = 
+/**
+ * No session, or it expired
+ */
+{ kind: "unauthorized"; message: string } | { kind: "notFound"; message: string } | 
+/**
+ * The arguments of the command are invalid
+ */
+{ kind: "validation"; message: string } | { kind: "db"; message: string } | { kind: "io"; message: string } | 
+/**
+ * A sidecar, the transcription backend or another external service failed
+ */
+{ kind: "backend"; message: string }
+export type AppSettings = { id: number; currentUserId: string | null; theme: string; language: string; selectedModel: string; modelProxy: string | null; lastLogin: string | null; autoLogin: boolean; maxConcurrentDownloads: number; transcriptionBackend: TranscriptionBackendKind; sessionIdleSeconds: number; sessionAbsoluteSeconds: number }
+export type AudioListItem = { id: string; title: string; description: string | null; url: string; thumbnail: string | null; startTime: number; endTime: number; provider: Provider; tag: string | null; transcribe: number; initialPrompt: string | null; language: string | null; updatedAt: string }
+/**
+ * Practice state of one segment of an audio: whether it's bookmarked and
+ * dictated, and how the dictation attempts went.
+ */
+export type BookmarkDictationView = { userId: string; audioId: string; segmentIndex: number; 
+/**
+ * `bookmark.id`, which is also the review card id
+ */
+bookmarkId: number | null; bookmarkCreatedAt: string | null; dictationId: number | null; dictationCreatedAt: string | null; attemptCount: number; latestAccuracy: number | null; latestAttemptAt: string | null }
+export type CreateAudioData = { audio_id: string; title: string; description: string | null; url: string; thumbnail: string; start_time: number; end_time: number; provider: Provider; tag: string | null }
+/**
+ * The signed in user as the frontend sees it.
+ */
+export type CurrentUser = { userId: string; name: string; email: string; picture: string | null }
+export type DictationAttempt = { id: number; audioId: string; segmentIndex: number; expectedText: string; typedText: string; marks: WordMark[]; accuracy: number; createdAt: string }
+export type DownloadJobInfo = { jobId: string; url: string; start: number; end: number; startedAt: number }
+export type DownloadQueueItem = { id: string; userId: string; url: string; title: string; description: string | null; thumbnail: string | null; startTime: number; endTime: number; provider: Provider; tag: string | null; status: string; attempts: number; error: string | null; nextAttemptAt: number; createdAt: string; updatedAt: string }
+export type EnqueueDownloadRequest = { url: string; title: string; description: string | null; thumbnail: string | null; startTime: number; endTime: number; tag: string | null }
+export type ExportTranscriptOptions = { 
+/**
+ * Adds inline word timestamps to WebVTT cues
+ */
+wordTiming: boolean; 
+/**
+ * Shifts the times by the clip's `startTime`, so they line up with the
+ * original video instead of the clip
+ */
+sourceOffset: boolean }
+export type MissedWord = { word: string; count: number }
+/**
+ * Where the frontend sends the browser to sign in, the loopback server on
+ * `port` receives the redirect.
+ */
+export type OAuthStart = { port: number; authUrl: string }
+export type PracticePeriod = { 
+/**
+ * First day of the day/week, `YYYY-MM-DD`
+ */
+start: string; minutesPracticed: number; segmentsDictated: number; attempts: number; averageAccuracy: number | null; bookmarksAdded: number; audiosAdded: number }
+export type PracticeStats = { range: StatsRange; periods: PracticePeriod[]; minutesPracticed: number; segmentsDictated: number; attempts: number; averageAccuracy: number | null; mostMissedWords: MissedWord[]; 
+/**
+ * Days in a row with practice up to today (or yesterday, until the user practices today)
+ */
+currentStreak: number; longestStreak: number }
+/**
+ * Where an audio item came from. Stored lowercase in `audio.provider`.
+ */
+export type Provider = "youtube" | "ytdlp" | "http" | "local"
+/**
+ * A bookmarked segment as a review card, `id` is the `bookmark.id`.
+ */
+export type ReviewCard = { id: number; audioId: string; audioTitle: string; bookmarkId: number; 
+/**
+ * `None` while the audio has no transcript
+ */
+text: string | null; startTime: number | null; endTime: number | null; easeFactor: number; intervalDays: number; repetitions: number; 
+/**
+ * `None` for cards that were never reviewed
+ */
+dueAt: string | null; lastReviewedAt: string | null }
+export type SectionDownload = { type: "Downloaded"; audio_id: string } | 
+/**
+ * The user already has this clip, `audio` can be reused instead
+ */
+{ type: "Duplicate"; audio: AudioListItem }
+export type Segment = { start: number; end: number; text: string; 
+/**
+ * Word-level timings, only present when the transcript was aligned
+ */
+words?: Word[] | null }
+/**
+ * A session as listed to its user, without the token.
+ */
+export type SessionInfo = { id: string; createdAt: string; lastActiveAt: string; expiresAt: number; absoluteExpiresAt: number; 
+/**
+ * The session of this app
+ */
+current: boolean }
+/**
+ * Where `split_transcript_segment` cuts a segment.
+ */
+export type SplitPoint = 
+/**
+ * Before the word at `index`, needs word timings
+ */
+{ type: "Word"; index: number } | 
+/**
+ * At `at` seconds, the text is cut after `text_offset` characters
+ */
+{ type: "Time"; at: number; text_offset: number }
+export type StatsRange = 
+/**
+ * The last 7 days, by day
+ */
+"week" | 
+/**
+ * The last 30 days, by day
+ */
+"month" | 
+/**
+ * The last 52 weeks, by week
+ */
+"year" | 
+/**
+ * Everything, by week
+ */
+"all"
+export type SubtitleFormat = "srt" | "vtt" | "tsv" | "txt"
+export type TranscribeOptions = { 
+/**
+ * Defaults to the model selected in the settings
+ */
+model: string | null; 
+/**
+ * A language code or `auto`, defaults to the audio's language and then
+ * the app language
+ */
+language: string | null; initialPrompt: string | null }
+export type TranscriptRevision = { id: number; audioId: string; userId: string; action: string; segmentIndex: number; oldSegments: Segment[]; newSegments: Segment[]; undone: boolean; createdAt: string }
+export type TranscriptSegment = { id: number; audioId: string; segmentIndex: number; startTime: number; endTime: number; text: string; words: Word[] | null }
+/**
+ * Which backend `transcribe` uses. Stored lowercase in `app_settings.transcriptionBackend`.
+ */
+export type TranscriptionBackendKind = "sidecar" | "service"
+export type TranscriptionJob = { audioId: string; userId: string; status: string; model: string; language: string; progress: number | null; message: string | null; error: string | null; createdAt: string; updatedAt: string }
+export type UpdateSettingsRequest = { theme: string | null; language: string | null; selectedModel: string | null; modelProxy: string | null; autoLogin: boolean | null; maxConcurrentDownloads: number | null; transcriptionBackend: TranscriptionBackendKind | null; 
+/**
+ * Sign out after this long without using the app
+ */
+sessionIdleSeconds: number | null; 
+/**
+ * Sign out this long after signing in
+ */
+sessionAbsoluteSeconds: number | null }
+export type VideoChapter = { title: string; startTime: number; endTime: number }
+export type VideoInfo = { id: string; title: string; description: string | null; uploader: string | null; duration: number | null; thumbnail: string | null; chapters: VideoChapter[]; subtitleLanguages: string[]; automaticCaptionLanguages: string[] }
+export type Word = { word: string; start: number | null; end: number | null; score: number | null }
+export type WordMark = { kind: WordMarkKind; expected: string | null; typed: string | null }
+export type WordMarkKind = "correct" | 
+/**
+ * In the transcript but not typed
+ */
+"missing" | 
+/**
+ * Typed but not in the transcript
+ */
+"extra" | 
+/**
+ * Typed close to the transcript word, but not quite
+ */
+"misspelled"
 
 /** tauri-specta globals **/
 
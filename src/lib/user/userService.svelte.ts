@@ -4,7 +4,6 @@ export const userKey = Symbol("user");
 
 export type UserInfo = {
     userId: string | null;
-    accessToken: string | null;
     name: string | null;
     email: string | null;
     picture: string | null;